@@ -0,0 +1,171 @@
+use super::ProvideAliases;
+use crate::common::AssumeIdentifier;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Serialization formats supported for alias files, resolved from the file extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasFileFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl AliasFileFormat {
+    pub const EXTENSIONS: [&'static str; 4] = ["json", "toml", "yaml", "yml"];
+
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml") | Some("yml") => Ok(Self::Yaml),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unsupported alias file {path:?}, expected one of the extensions: {}",
+                    Self::EXTENSIONS.join(", ")
+                ),
+            )),
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> io::Result<BTreeMap<String, AccountRole>> {
+        match self {
+            Self::Json => Ok(serde_json::from_str(content)?),
+            Self::Toml => toml::from_str(content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Self::Yaml => serde_yaml::from_str(content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+
+    pub fn serialize(&self, aliases: &BTreeMap<String, AccountRole>) -> io::Result<String> {
+        match self {
+            Self::Json => Ok(serde_json::to_string(aliases)?),
+            Self::Toml => toml::to_string(aliases)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Self::Yaml => serde_yaml::to_string(aliases)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountRole {
+    #[serde(rename = "accountId")]
+    pub account: String,
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+}
+
+/// Alias provider backed by a single JSON, TOML or YAML file.
+/// A missing file is treated as an empty set of aliases.
+#[derive(Debug)]
+pub struct FileAliasProvider {
+    file_path: PathBuf,
+    format: AliasFileFormat,
+    aliases: BTreeMap<String, AccountRole>,
+}
+
+impl FileAliasProvider {
+    pub fn new(file_path: PathBuf) -> io::Result<Self> {
+        let format = AliasFileFormat::from_path(&file_path)?;
+        Ok(FileAliasProvider {
+            file_path,
+            format,
+            aliases: BTreeMap::new(),
+        })
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    fn save_aliases(&self) -> io::Result<()> {
+        let content = self.format.serialize(&self.aliases)?;
+        let mut file = File::create(&self.file_path)?;
+        file.write_all(content.as_bytes())
+    }
+}
+
+impl ProvideAliases for FileAliasProvider {
+    type Error = io::Error;
+
+    fn load_aliases(&mut self) -> io::Result<()> {
+        if self.file_path.exists() {
+            let mut content = String::new();
+            File::open(&self.file_path)?.read_to_string(&mut content)?;
+            self.aliases = self.format.parse(&content)?;
+        }
+        Ok(())
+    }
+
+    fn set_alias(
+        &mut self,
+        alias: &str,
+        account: &str,
+        role: &str,
+        region: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        let ai = AccountRole {
+            account: account.to_string(),
+            role: role.to_string(),
+            region: region.map(ToString::to_string),
+        };
+        self.aliases.insert(alias.to_string(), ai);
+        self.save_aliases()
+    }
+
+    fn set_aliases(&mut self, aliases: &[(&str, AssumeIdentifier<'_>)]) -> Result<(), Self::Error> {
+        for (alias, assume_identifier) in aliases {
+            self.aliases.insert(
+                alias.to_string(),
+                AccountRole {
+                    account: assume_identifier.account.to_string(),
+                    role: assume_identifier.role.to_string(),
+                    region: assume_identifier.region.map(ToString::to_string),
+                },
+            );
+        }
+        self.save_aliases()
+    }
+
+    fn unset_alias(&mut self, alias: &str) -> Result<(), Self::Error> {
+        self.aliases.remove(alias);
+        self.save_aliases()
+    }
+
+    fn replace_aliases(
+        &mut self,
+        aliases: &[(&str, AssumeIdentifier<'_>)],
+    ) -> Result<(), Self::Error> {
+        self.aliases.clear();
+        self.set_aliases(aliases)
+    }
+
+    fn list_aliases(&self) -> Result<Vec<[&str; 3]>, Self::Error> {
+        Ok(self
+            .aliases
+            .iter()
+            .map(|(alias, account_role)| {
+                [
+                    alias.as_str(),
+                    account_role.account.as_str(),
+                    account_role.role.as_str(),
+                ]
+            })
+            .collect())
+    }
+
+    fn get_alias(&self, alias: &str) -> Result<Option<AssumeIdentifier<'_>>, Self::Error> {
+        Ok(self.aliases.get(alias).map(|a| AssumeIdentifier {
+            account: &a.account,
+            role: &a.role,
+            region: a.region.as_deref(),
+        }))
+    }
+}
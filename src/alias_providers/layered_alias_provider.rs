@@ -0,0 +1,225 @@
+use super::file_alias_provider::FileAliasProvider;
+use super::ProvideAliases;
+use crate::common::AssumeIdentifier;
+use std::collections::BTreeMap;
+use std::io;
+
+/// Source of an alias in a layered setup, ordered from lowest to highest precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasLayer {
+    /// Machine wide aliases, usually managed by an administrator
+    System,
+    /// Shared team catalogue, usually checked into git
+    Team,
+    /// User aliases stored in the config directory, the only writable layer
+    Personal,
+}
+
+impl AliasLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AliasLayer::System => "system",
+            AliasLayer::Team => "team",
+            AliasLayer::Personal => "personal",
+        }
+    }
+}
+
+impl std::fmt::Display for AliasLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug)]
+struct ReadOnlyLayer {
+    layer: AliasLayer,
+    provider: FileAliasProvider,
+    required: bool,
+}
+
+/// Merges aliases from multiple files. Aliases in higher precedence layers shadow
+/// aliases with the same name in lower ones (personal > team > system).
+/// Writes always go to the personal layer.
+#[derive(Debug)]
+pub struct LayeredAliasProvider {
+    personal: FileAliasProvider,
+    // Ordered from highest to lowest precedence
+    read_only: Vec<ReadOnlyLayer>,
+}
+
+impl LayeredAliasProvider {
+    pub fn new(personal: FileAliasProvider) -> Self {
+        Self {
+            personal,
+            read_only: Vec::new(),
+        }
+    }
+
+    /// Adds a read-only layer below the layers added so far.
+    /// Loading fails if a required layer's file does not exist.
+    pub fn with_read_only_layer(
+        mut self,
+        layer: AliasLayer,
+        provider: FileAliasProvider,
+        required: bool,
+    ) -> Self {
+        self.read_only.push(ReadOnlyLayer {
+            layer,
+            provider,
+            required,
+        });
+        self
+    }
+
+    fn layers(&self) -> impl Iterator<Item = (AliasLayer, &FileAliasProvider)> {
+        std::iter::once((AliasLayer::Personal, &self.personal))
+            .chain(self.read_only.iter().map(|ro| (ro.layer, &ro.provider)))
+    }
+
+    /// Returns the layer the effective definition of the alias comes from.
+    pub fn get_alias_layer(&self, alias: &str) -> io::Result<Option<AliasLayer>> {
+        for (layer, provider) in self.layers() {
+            if provider.get_alias(alias)?.is_some() {
+                return Ok(Some(layer));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Lists effective aliases sorted by name as `[alias, account, role, layer]`.
+    pub fn list_aliases_with_layer(&self) -> io::Result<Vec<[&str; 4]>> {
+        let mut merged: BTreeMap<&str, [&str; 4]> = BTreeMap::new();
+        let layers = self.layers().collect::<Vec<_>>();
+        for (layer, provider) in layers.into_iter().rev() {
+            for [alias, account, role] in provider.list_aliases()? {
+                merged.insert(alias, [alias, account, role, layer.as_str()]);
+            }
+        }
+        Ok(merged.into_values().collect())
+    }
+}
+
+impl ProvideAliases for LayeredAliasProvider {
+    type Error = io::Error;
+
+    fn load_aliases(&mut self) -> io::Result<()> {
+        for ro in self.read_only.iter_mut() {
+            if ro.required && !ro.provider.file_path().exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{} alias file {:?} does not exist",
+                        ro.layer,
+                        ro.provider.file_path()
+                    ),
+                ));
+            }
+            ro.provider.load_aliases()?;
+        }
+        self.personal.load_aliases()
+    }
+
+    fn set_alias(
+        &mut self,
+        alias: &str,
+        account: &str,
+        role: &str,
+        region: Option<&str>,
+    ) -> Result<(), Self::Error> {
+        self.personal.set_alias(alias, account, role, region)
+    }
+
+    fn set_aliases(&mut self, aliases: &[(&str, AssumeIdentifier<'_>)]) -> Result<(), Self::Error> {
+        self.personal.set_aliases(aliases)
+    }
+
+    fn unset_alias(&mut self, alias: &str) -> Result<(), Self::Error> {
+        if self.personal.get_alias(alias)?.is_none() {
+            if let Some(layer) = self.get_alias_layer(alias)? {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("Alias {alias} is defined in the read-only {layer} layer"),
+                ));
+            }
+        }
+        self.personal.unset_alias(alias)
+    }
+
+    fn replace_aliases(
+        &mut self,
+        aliases: &[(&str, AssumeIdentifier<'_>)],
+    ) -> Result<(), Self::Error> {
+        self.personal.replace_aliases(aliases)
+    }
+
+    fn list_aliases(&self) -> Result<Vec<[&str; 3]>, Self::Error> {
+        Ok(self
+            .list_aliases_with_layer()?
+            .into_iter()
+            .map(|[alias, account, role, _]| [alias, account, role])
+            .collect())
+    }
+
+    fn get_alias(&self, alias: &str) -> Result<Option<AssumeIdentifier<'_>>, Self::Error> {
+        for (_, provider) in self.layers() {
+            if let Some(assume_identifier) = provider.get_alias(alias)? {
+                return Ok(Some(assume_identifier));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_layer_precedence_and_writes() {
+        let dir = std::env::temp_dir().join(format!("aws-auth-layered-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("system.yaml"),
+            "shared:\n  accountId: '111111111111'\n  role: SystemRole\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("team.toml"),
+            "[shared]\naccountId = \"222222222222\"\nrole = \"TeamRole\"\n\n[team-only]\naccountId = \"222222222222\"\nrole = \"ReadOnly\"\n",
+        )
+        .unwrap();
+
+        let mut provider =
+            LayeredAliasProvider::new(FileAliasProvider::new(dir.join("aliases.json")).unwrap())
+                .with_read_only_layer(
+                    AliasLayer::Team,
+                    FileAliasProvider::new(dir.join("team.toml")).unwrap(),
+                    true,
+                )
+                .with_read_only_layer(
+                    AliasLayer::System,
+                    FileAliasProvider::new(dir.join("system.yaml")).unwrap(),
+                    true,
+                );
+        provider.load_aliases().unwrap();
+
+        let shared = provider.get_alias("shared").unwrap().unwrap();
+        assert_eq!(shared.role, "TeamRole");
+        assert!(provider.unset_alias("team-only").is_err());
+
+        provider
+            .set_alias("shared", "333333333333", "PersonalRole", None)
+            .unwrap();
+        assert_eq!(
+            provider.list_aliases_with_layer().unwrap(),
+            vec![
+                ["shared", "333333333333", "PersonalRole", "personal"],
+                ["team-only", "222222222222", "ReadOnly", "team"],
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,119 +1,99 @@
+pub mod file_alias_provider;
+pub mod layered_alias_provider;
+
 use std::io;
 
 use crate::common::AssumeIdentifier;
-use json_alias_provider::JsonAliasProvider;
-use std::path::Path;
+use file_alias_provider::{AliasFileFormat, FileAliasProvider};
+use layered_alias_provider::{AliasLayer, LayeredAliasProvider};
+use std::env;
+use std::path::{Path, PathBuf};
 
-pub type AliasProvider = JsonAliasProvider;
+pub type AliasProvider = LayeredAliasProvider;
 pub type AliasProviderError = io::Error;
 
+const ALIASES_FILE_STEM: &str = "aliases";
+const SYSTEM_ALIASES_ENV: &str = "AWS_AUTH_SYSTEM_ALIASES";
+
 pub trait ProvideAliases {
     type Error: std::error::Error;
     fn get_alias(&self, alias: &str) -> Result<Option<AssumeIdentifier<'_>>, Self::Error>;
     fn list_aliases(&self) -> Result<Vec<[&'_ str; 3]>, Self::Error>;
     fn load_aliases(&mut self) -> Result<(), Self::Error>;
-    fn set_alias(&mut self, alias: &str, account: &str, role: &str) -> Result<(), Self::Error>;
+    fn set_alias(
+        &mut self,
+        alias: &str,
+        account: &str,
+        role: &str,
+        region: Option<&str>,
+    ) -> Result<(), Self::Error>;
+    fn set_aliases(&mut self, aliases: &[(&str, AssumeIdentifier<'_>)]) -> Result<(), Self::Error>;
     fn unset_alias(&mut self, alias: &str) -> Result<(), Self::Error>;
+    /// Replaces all aliases of the writable storage of the provider, saved at once.
+    fn replace_aliases(
+        &mut self,
+        aliases: &[(&str, AssumeIdentifier<'_>)],
+    ) -> Result<(), Self::Error>;
 }
 
-pub fn build_alias_provider(config_dir: &Path) -> AliasProvider {
-    JsonAliasProvider::new(config_dir.join("aliases.json"))
-}
-
-pub fn build_alias_provider_and_load(
-    config_dir: &Path,
-) -> Result<AliasProvider, AliasProviderError> {
-    let mut provider = JsonAliasProvider::new(config_dir.join("aliases.json"));
-    provider.load_aliases()?;
-    Ok(provider)
+/// Returns the first existing `aliases.<ext>` file in the directory, in extension priority order.
+fn find_alias_file(dir: &Path) -> Option<PathBuf> {
+    AliasFileFormat::EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(ALIASES_FILE_STEM).with_extension(ext))
+        .find(|path| path.exists())
 }
 
-pub mod json_alias_provider {
-
-    use serde::{Deserialize, Serialize};
-
-    use super::ProvideAliases;
-    use crate::common::AssumeIdentifier;
-    use std::collections::HashMap;
-    use std::fs::File;
-    use std::io;
-    use std::path::PathBuf;
-
-    #[derive(Serialize, Deserialize, Debug)]
-    struct AccountRole {
-        #[serde(rename = "accountId")]
-        account: String,
-        role: String,
+fn system_alias_dir() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env::var_os("ProgramData").map(|dir| PathBuf::from(dir).join("aws-auth"))
+    } else {
+        Some(PathBuf::from("/etc/aws-auth"))
     }
+}
 
-    #[derive(Debug)]
-    pub struct JsonAliasProvider {
-        file_path: PathBuf,
-        aliases: HashMap<String, AccountRole>,
+/// Builds the layered alias provider:
+/// - system: `$AWS_AUTH_SYSTEM_ALIASES` or `aliases.<ext>` in /etc/aws-auth (%ProgramData%\aws-auth on windows)
+/// - team: the provided team aliases file
+/// - personal: `aliases.<ext>` in the config directory, defaults to aliases.json
+pub fn build_alias_provider(
+    config_dir: &Path,
+    team_aliases: Option<&Path>,
+) -> Result<AliasProvider, AliasProviderError> {
+    let personal_path = find_alias_file(config_dir)
+        .unwrap_or_else(|| config_dir.join(ALIASES_FILE_STEM).with_extension("json"));
+    let mut provider = LayeredAliasProvider::new(FileAliasProvider::new(personal_path)?);
+
+    if let Some(team_aliases) = team_aliases {
+        provider = provider.with_read_only_layer(
+            AliasLayer::Team,
+            FileAliasProvider::new(team_aliases.to_path_buf())?,
+            true,
+        );
     }
 
-    impl JsonAliasProvider {
-        pub fn new(file_path: PathBuf) -> Self {
-            JsonAliasProvider {
-                file_path,
-                aliases: HashMap::new(),
-            }
-        }
-        fn save_aliases(&self) -> io::Result<()> {
-            let file = File::create(&self.file_path)?;
-            serde_json::to_writer(file, &self.aliases)?;
-            Ok(())
-        }
+    if let Some(system_path) = env::var_os(SYSTEM_ALIASES_ENV) {
+        provider = provider.with_read_only_layer(
+            AliasLayer::System,
+            FileAliasProvider::new(PathBuf::from(system_path))?,
+            true,
+        );
+    } else if let Some(system_path) = system_alias_dir().as_deref().and_then(find_alias_file) {
+        provider = provider.with_read_only_layer(
+            AliasLayer::System,
+            FileAliasProvider::new(system_path)?,
+            false,
+        );
     }
 
-    impl ProvideAliases for JsonAliasProvider {
-        type Error = io::Error;
-
-        fn load_aliases(&mut self) -> io::Result<()> {
-            if self.file_path.exists() {
-                let file = File::open(&self.file_path)?;
-                let reader = io::BufReader::new(file);
-                self.aliases = serde_json::from_reader::<
-                    io::BufReader<File>,
-                    HashMap<String, AccountRole>,
-                >(reader)?;
-            }
-            Ok(())
-        }
-
-        fn set_alias(&mut self, alias: &str, account: &str, role: &str) -> Result<(), Self::Error> {
-            let ai = AccountRole {
-                account: account.to_string(),
-                role: role.to_string(),
-            };
-            self.aliases.insert(alias.to_string(), ai);
-            self.save_aliases()
-        }
-
-        fn unset_alias(&mut self, alias: &str) -> Result<(), Self::Error> {
-            self.aliases.remove(alias);
-            self.save_aliases()
-        }
-
-        fn list_aliases(&self) -> Result<Vec<[&str; 3]>, Self::Error> {
-            Ok(self
-                .aliases
-                .iter()
-                .map(|(alias, account_role)| {
-                    [
-                        alias.as_str(),
-                        account_role.account.as_str(),
-                        account_role.role.as_str(),
-                    ]
-                })
-                .collect())
-        }
+    Ok(provider)
+}
 
-        fn get_alias(&self, alias: &str) -> Result<Option<AssumeIdentifier<'_>>, Self::Error> {
-            Ok(self.aliases.get(alias).map(|a| AssumeIdentifier {
-                account: &a.account,
-                role: &a.role,
-            }))
-        }
-    }
+pub fn build_alias_provider_and_load(
+    config_dir: &Path,
+    team_aliases: Option<&Path>,
+) -> Result<AliasProvider, AliasProviderError> {
+    let mut provider = build_alias_provider(config_dir, team_aliases)?;
+    provider.load_aliases()?;
+    Ok(provider)
 }
@@ -2,7 +2,9 @@ use super::cache::CacheRefMut;
 use crate::aws_sso::cache::ManageCache;
 use crate::aws_sso::types::ClientInformation;
 use crate::utils::lock::CounterLockProvider;
+use crate::utils::rate_limit::{jittered_backoff, RateLimiter};
 use aws_config::{AppName, BehaviorVersion, Region, SdkConfig};
+use aws_sdk_sso::error::ProvideErrorMetadata;
 use aws_sdk_sso::operation::get_role_credentials::GetRoleCredentialsError;
 use aws_sdk_sso::operation::list_account_roles::ListAccountRolesError;
 use aws_sdk_sso::operation::list_accounts::ListAccountsError;
@@ -15,15 +17,23 @@ use aws_sdk_ssooidc::{config::Credentials, Client as OidcClient};
 use aws_smithy_runtime_api::client::result::SdkError;
 use aws_smithy_runtime_api::http::Response;
 use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
 use std::thread;
 use std::time::UNIX_EPOCH;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
 
 const OIDC_APP_NAME: &str = "aws-auth";
 const OIDC_CLIENT_TYPE: &str = "public";
 const GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
-const DEFAULT_CREATE_TOKEN_INITIAL_DELAY: Duration = Duration::seconds(10);
-const DEFAULT_CREATE_TOKEN_RETRY_INTERVAL: Duration = Duration::seconds(5);
-const DEFAULT_CREATE_TOKEN_MAX_ATTEMPTS: usize = 10;
+pub const DEFAULT_CREATE_TOKEN_INITIAL_DELAY: Duration = Duration::seconds(10);
+pub const DEFAULT_CREATE_TOKEN_RETRY_INTERVAL: Duration = Duration::seconds(5);
+pub const DEFAULT_CREATE_TOKEN_MAX_ATTEMPTS: usize = 10;
+pub const DEFAULT_SSO_REQUEST_CONCURRENCY: usize = 8;
+pub const DEFAULT_SSO_REQUEST_RATE: f64 = 10.0;
+pub const DEFAULT_SSO_REQUEST_MAX_RETRIES: u32 = 5;
+const SSO_REQUEST_BACKOFF_BASE: std::time::Duration = std::time::Duration::from_millis(250);
+const SSO_REQUEST_BACKOFF_CAP: std::time::Duration = std::time::Duration::from_secs(10);
 const EXPECT_MESSAGE: &str = "Should be present, caller pub function assume_role asures it";
 
 #[derive(Debug)]
@@ -87,8 +97,140 @@ impl<
 {
 }
 
+impl<
+        CE: 'static + std::error::Error + std::fmt::Debug,
+        LE: 'static + std::error::Error + std::fmt::Debug,
+    > Error<CE, LE>
+{
+    /// True when SSO denied access to the account or role, e.g. a role the user is not assigned,
+    /// rather than failing the request.
+    pub fn is_access_denied(&self) -> bool {
+        match self {
+            Error::SsoGetRoleCredentials(err) => is_access_denied(err),
+            Error::OidcListAccountRoles(err) => is_access_denied(err),
+            _ => false,
+        }
+    }
+}
+
+fn is_access_denied<E: ProvideErrorMetadata>(err: &SdkError<E, Response>) -> bool {
+    err.as_service_error()
+        .and_then(ProvideErrorMetadata::code)
+        .is_some_and(|code| {
+            matches!(
+                code,
+                "ForbiddenException" | "UnauthorizedException" | "AccessDeniedException"
+            )
+        })
+}
+
 type Result<T, CE, LE> = std::result::Result<T, Error<CE, LE>>;
 
+async fn get_role_credentials(
+    sso_client: &SsoClient,
+    access_token: &str,
+    account_id: &str,
+    role_name: &str,
+) -> std::result::Result<Credentials, SdkError<GetRoleCredentialsError, Response>> {
+    let credentials = sso_client
+        .get_role_credentials()
+        .role_name(role_name)
+        .account_id(account_id)
+        .access_token(access_token)
+        .send()
+        .await?
+        .role_credentials
+        .expect("Exit early if GetRoleCredentials fails, role credentials should be present");
+
+    Ok(Credentials::new(
+        credentials
+            .access_key_id
+            .expect("Should be present, Succesfull GetRoleCredentials assures it"),
+        credentials
+            .secret_access_key
+            .expect("Should be present, Succesfull GetRoleCredentials assures it"),
+        credentials.session_token,
+        Some(
+            UNIX_EPOCH
+                + std::time::Duration::from_millis(credentials.expiration.try_into().unwrap()),
+        ),
+        "role-credentials",
+    ))
+}
+
+/// Limits of the concurrent SSO requests made when resolving many accounts
+#[derive(Debug, Clone, Copy)]
+pub struct SsoRequestLimits {
+    /// Requests in flight at once
+    pub concurrency: usize,
+    /// Requests started per second, zero disables the limit
+    pub requests_per_second: f64,
+    /// Retries of a request throttled with TooManyRequestsException
+    pub max_retries: u32,
+}
+
+impl Default for SsoRequestLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_SSO_REQUEST_CONCURRENCY,
+            requests_per_second: DEFAULT_SSO_REQUEST_RATE,
+            max_retries: DEFAULT_SSO_REQUEST_MAX_RETRIES,
+        }
+    }
+}
+
+/// Sends the request once the rate limiter allows it, retrying requests throttled with
+/// TooManyRequestsException after a jittered backoff.
+async fn send_rate_limited<T, E, F, Fut>(
+    rate_limiter: &RateLimiter,
+    max_retries: u32,
+    is_throttled: fn(&E) -> bool,
+    mut send: F,
+) -> std::result::Result<T, SdkError<E, Response>>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = std::result::Result<T, SdkError<E, Response>>>,
+{
+    let mut attempt = 0;
+    loop {
+        rate_limiter.acquire().await;
+        match send().await {
+            Err(err)
+                if attempt < max_retries && err.as_service_error().is_some_and(is_throttled) =>
+            {
+                tokio::time::sleep(jittered_backoff(
+                    attempt,
+                    SSO_REQUEST_BACKOFF_BASE,
+                    SSO_REQUEST_BACKOFF_CAP,
+                ))
+                .await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn list_account_role_names(
+    sso_client: &SsoClient,
+    access_token: &str,
+    account_id: &str,
+) -> std::result::Result<Vec<String>, SdkError<ListAccountRolesError, Response>> {
+    Ok(sso_client
+        .list_account_roles()
+        .account_id(account_id)
+        .access_token(access_token)
+        .into_paginator()
+        .send()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .await?
+        .into_iter()
+        .filter_map(|res| res.role_list)
+        .flatten()
+        .filter_map(|role| role.role_name)
+        .collect())
+}
+
 pub struct AuthManager<'a, C, L>
 where
     C: 'static + ManageCache,
@@ -259,6 +401,54 @@ where
         .await
     }
 
+    /// Lists the roles of every account with a single SSO login, concurrently within `limits`.
+    /// Results follow the order of `account_ids`.
+    pub async fn list_roles_of_accounts(
+        &mut self,
+        account_ids: &[String],
+        limits: SsoRequestLimits,
+        ignore_cache: bool,
+    ) -> Result<Vec<Result<Vec<String>, C::Error, L::Error>>, C::Error, L::Error> {
+        self.prepare_sso_and_resolve(
+            async |auth| {
+                let access_token = auth.client_info.access_token.clone().expect(EXPECT_MESSAGE);
+                let semaphore = Arc::new(Semaphore::new(limits.concurrency.max(1)));
+                let rate_limiter = Arc::new(RateLimiter::new(limits.requests_per_second));
+                let mut tasks = JoinSet::new();
+                for (index, account_id) in account_ids.iter().enumerate() {
+                    let sso_client = auth.sso_client.clone();
+                    let access_token = access_token.clone();
+                    let account_id = account_id.clone();
+                    let semaphore = semaphore.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    tasks.spawn(async move {
+                        let _permit = semaphore
+                            .acquire()
+                            .await
+                            .expect("Semaphore is never closed");
+                        let roles = send_rate_limited(
+                            &rate_limiter,
+                            limits.max_retries,
+                            ListAccountRolesError::is_too_many_requests_exception,
+                            || list_account_role_names(&sso_client, &access_token, &account_id),
+                        )
+                        .await;
+                        (index, roles)
+                    });
+                }
+                let mut results = account_ids.iter().map(|_| None).collect::<Vec<_>>();
+                while let Some(joined) = tasks.join_next().await {
+                    let (index, roles) = joined.expect("ListAccountRoles task panicked");
+                    results[index] = Some(roles.map_err(Error::OidcListAccountRoles));
+                }
+                // Every account has a result once all tasks joined
+                Ok(results.into_iter().flatten().collect())
+            },
+            ignore_cache,
+        )
+        .await
+    }
+
     pub async fn assume_role(
         &mut self,
         account_id: &str,
@@ -286,6 +476,150 @@ where
         .await
     }
 
+    /// Assumes every account role with a single SSO login. Cached sessions are reused unless
+    /// `refresh_sts_token`, the remaining GetRoleCredentials calls run concurrently with at most
+    /// `concurrency` in flight. Results follow the order of `account_roles`.
+    pub async fn assume_roles(
+        &mut self,
+        account_roles: &[(String, String)],
+        concurrency: usize,
+        refresh_sts_token: bool,
+        ignore_cache: bool,
+    ) -> Result<Vec<Result<Credentials, C::Error, L::Error>>, C::Error, L::Error> {
+        let candidates = account_roles
+            .iter()
+            .map(|(account_id, role_name)| (account_id.clone(), vec![role_name.clone()]))
+            .collect::<Vec<_>>();
+        let limits = SsoRequestLimits {
+            concurrency,
+            ..SsoRequestLimits::default()
+        };
+        let resolved = self
+            .assume_first_roles(
+                &candidates,
+                limits,
+                refresh_sts_token,
+                ignore_cache,
+                |_, _| {},
+            )
+            .await?;
+        let mut results = Vec::with_capacity(resolved.len());
+        for result in resolved {
+            results.push(result.map(|(_, credentials)| credentials));
+        }
+        Ok(results)
+    }
+
+    /// Assumes the first role each account allows, trying the roles in the given order, with a
+    /// single SSO login. Accounts are resolved concurrently within `limits`, throttled
+    /// GetRoleCredentials calls are retried with jittered backoff. `on_resolved` is called with
+    /// the index of each account as soon as it is resolved. Results follow the order of
+    /// `candidates` and hold the role that was assumed.
+    pub async fn assume_first_roles<F>(
+        &mut self,
+        candidates: &[(String, Vec<String>)],
+        limits: SsoRequestLimits,
+        refresh_sts_token: bool,
+        ignore_cache: bool,
+        mut on_resolved: F,
+    ) -> Result<Vec<Result<(String, Credentials), C::Error, L::Error>>, C::Error, L::Error>
+    where
+        F: FnMut(usize, &Result<(String, Credentials), C::Error, L::Error>),
+    {
+        self.prepare_sso_and_resolve(
+            async |auth| {
+                let access_token = auth.client_info.access_token.clone().expect(EXPECT_MESSAGE);
+                let semaphore = Arc::new(Semaphore::new(limits.concurrency.max(1)));
+                let rate_limiter = Arc::new(RateLimiter::new(limits.requests_per_second));
+                let mut results = Vec::with_capacity(candidates.len());
+                let mut tasks = JoinSet::new();
+                for (index, (account_id, role_names)) in candidates.iter().enumerate() {
+                    results.push(None);
+                    // Roles keep their priority, a cached session only wins when no role before it succeeds
+                    let roles = role_names
+                        .iter()
+                        .map(|role_name| {
+                            let cached = auth
+                                .cache_manager
+                                .get_session(account_id, role_name)
+                                .filter(|_| !refresh_sts_token)
+                                .map(|cached| Credentials::from(cached.clone()));
+                            (role_name.clone(), cached)
+                        })
+                        .collect::<Vec<_>>();
+                    let sso_client = auth.sso_client.clone();
+                    let access_token = access_token.clone();
+                    let account_id = account_id.clone();
+                    let semaphore = semaphore.clone();
+                    let rate_limiter = rate_limiter.clone();
+                    tasks.spawn(async move {
+                        let mut last_error = None;
+                        for (role_name, cached) in roles {
+                            if let Some(cached) = cached {
+                                return (index, Ok((role_name, cached, false)));
+                            }
+                            let _permit = semaphore
+                                .acquire()
+                                .await
+                                .expect("Semaphore is never closed");
+                            match send_rate_limited(
+                                &rate_limiter,
+                                limits.max_retries,
+                                GetRoleCredentialsError::is_too_many_requests_exception,
+                                || {
+                                    get_role_credentials(
+                                        &sso_client,
+                                        &access_token,
+                                        &account_id,
+                                        &role_name,
+                                    )
+                                },
+                            )
+                            .await
+                            {
+                                Ok(credentials) => {
+                                    return (index, Ok((role_name, credentials, true)))
+                                }
+                                // A failed request is reported over roles the account denied
+                                Err(err) => {
+                                    if last_error.as_ref().is_none_or(is_access_denied) {
+                                        last_error = Some(err)
+                                    }
+                                }
+                            }
+                        }
+                        (
+                            index,
+                            Err(last_error.expect("Accounts are assumed with at least one role")),
+                        )
+                    });
+                }
+                while let Some(joined) = tasks.join_next().await {
+                    let (index, resolved) = joined.expect("GetRoleCredentials task panicked");
+                    let result = match resolved {
+                        Ok((role_name, credentials, fetched)) => {
+                            if fetched {
+                                auth.cache_manager.set_session(
+                                    &candidates[index].0,
+                                    &role_name,
+                                    credentials.clone(),
+                                );
+                            }
+                            Ok((role_name, credentials))
+                        }
+                        Err(err) => Err(Error::SsoGetRoleCredentials(err)),
+                    };
+                    on_resolved(index, &result);
+                    results[index] = Some(result);
+                }
+                // Every account has a result once all tasks joined
+                Ok(results.into_iter().flatten().collect())
+            },
+            ignore_cache,
+        )
+        .await
+    }
+
     pub fn load_cache(&mut self, ignore_cache: bool) {
         if self.cache_manager.load_cache().is_err()
             || !self.cache_manager.is_valid(&self.start_url)
@@ -430,37 +764,17 @@ where
         role_name: &str,
         account_id: &str,
     ) -> Result<Credentials, C::Error, L::Error> {
-        let credentials = self
-            .sso_client
-            .get_role_credentials()
-            .role_name(role_name)
-            .account_id(account_id)
-            .access_token(
-                self.client_info
-                    .access_token
-                    .as_deref()
-                    .expect(EXPECT_MESSAGE),
-            )
-            .send()
-            .await
-            .map_err(Error::SsoGetRoleCredentials)?
-            .role_credentials
-            .expect("Exit early if GetRoleCredentials fails, role credentials should be present");
-
-        Ok(Credentials::new(
-            credentials
-                .access_key_id
-                .expect("Should be present, Succesfull GetRoleCredentials assures it"),
-            credentials
-                .secret_access_key
-                .expect("Should be present, Succesfull GetRoleCredentials assures it"),
-            credentials.session_token,
-            Some(
-                UNIX_EPOCH
-                    + std::time::Duration::from_millis(credentials.expiration.try_into().unwrap()),
-            ),
-            "role-credentials",
-        ))
+        get_role_credentials(
+            &self.sso_client,
+            self.client_info
+                .access_token
+                .as_deref()
+                .expect(EXPECT_MESSAGE),
+            account_id,
+            role_name,
+        )
+        .await
+        .map_err(Error::SsoGetRoleCredentials)
     }
 
     pub async fn logout(mut self) -> Result<(), C::Error, L::Error> {
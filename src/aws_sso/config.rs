@@ -1,53 +1,568 @@
+use crate::utils::region;
 use serde::{Deserialize, Serialize};
 use std::{
-    fs::File,
-    io::Read,
+    collections::BTreeMap,
+    env, fs,
     path::{Path, PathBuf},
     time::Duration,
 };
 
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const LEGACY_CONFIG_FILE_NAME: &str = "config.json";
+pub const CURRENT_CONFIG_VERSION: u32 = 1;
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".aws-auth.toml";
+pub const CONFIG_ENV_PREFIX: &str = "AWS_AUTH_";
+pub const CONFIG_KEYS: [&str; 13] = [
+    "version",
+    "start_url",
+    "sso_region",
+    "max_attempts",
+    "initial_delay",
+    "retry_interval",
+    "create_token_retry_threshold",
+    "create_token_lock_decay",
+    "default_alias",
+    "region",
+    "eks_cluster",
+    "batch_regions",
+    "role_preference",
+];
+/// Keys a project file may pin. SSO settings are excluded so a checked out repository
+/// cannot redirect the login to a different start URL, and role_preference so it cannot
+/// choose the role batch commands assume.
+pub const PROJECT_CONFIG_KEYS: [&str; 4] =
+    ["default_alias", "region", "eks_cluster", "batch_regions"];
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Invalid config due to missing fields or Invalid Syntax: {0}")]
-    InvalidConfig(#[from] serde_json::Error),
+    #[error("Invalid config at {:?}: {}", .0, .1)]
+    InvalidConfig(PathBuf, toml::de::Error),
+    #[error("Invalid legacy config at {:?} due to missing fields or Invalid Syntax: {}", .0, .1)]
+    InvalidLegacyConfig(PathBuf, serde_json::Error),
+    #[error("Config version {0} is not supported, latest supported version is {CURRENT_CONFIG_VERSION}. Upgrade aws-auth to use this config")]
+    UnsupportedVersion(u32),
     #[error("Config file not found at {:?}: {}. Run `aws-auth init --help` to get help initializing config", .0, .1)]
     ConfigNotFound(PathBuf, std::io::Error),
+    #[error("Error serializing config: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("Error writing config to {:?}: {}", .0, .1)]
+    Write(PathBuf, std::io::Error),
+    #[error("Unknown config key {0:?}, expected one of: {keys}", keys = CONFIG_KEYS.join(", "))]
+    UnknownKey(String),
+    #[error("Config key {0:?} cannot be changed")]
+    ReadOnlyKey(String),
+    #[error("Invalid value {value:?} for config key {key:?}: {reason}")]
+    InvalidValue {
+        key: String,
+        value: String,
+        reason: String,
+    },
+    #[error("Config key {1:?} in project file {0:?} is not supported, expected one of: {keys}", keys = PROJECT_CONFIG_KEYS.join(", "))]
+    UnsupportedProjectKey(PathBuf, String),
+    #[error("Invalid value in environment variable {0}: {1}")]
+    InvalidEnv(String, Box<Error>),
+}
+
+/// Origin of an effective config value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    ConfigFile(PathBuf),
+    ProjectFile(PathBuf),
+    Env(String),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigSource::ConfigFile(path) | ConfigSource::ProjectFile(path) => {
+                write!(f, "{}", path.display())
+            }
+            ConfigSource::Env(var) => write!(f, "${var}"),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Human readable durations such as "10s", "2h" or "1h 30m"
+mod human_duration {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(d) => serializer.serialize_str(&humantime::format_duration(*d).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| {
+                humantime::parse_duration(&s).map_err(|err| {
+                    serde::de::Error::custom(format!("invalid duration {s:?}: {err}"))
+                })
+            })
+            .transpose()
+    }
+}
+
+fn default_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
+}
+
 #[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct AwsSsoConfig {
-    #[serde(rename = "startURL")]
+    #[serde(default = "default_config_version")]
+    pub version: u32,
     pub start_url: String,
-    #[serde(rename = "ssoRegion")]
-    pub sso_reigon: String,
-    #[serde(rename = "maxAttempts", skip_serializing_if = "Option::is_none")]
+    pub sso_region: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub max_attempts: Option<usize>,
-    #[serde(rename = "initialDelay", skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        with = "human_duration",
+        skip_serializing_if = "Option::is_none"
+    )]
     pub initial_delay: Option<Duration>,
-    #[serde(rename = "retryInterval", skip_serializing_if = "Option::is_none")]
-    pub retry_interval: Option<Duration>,
     #[serde(
-        rename = "createTokenRetryThreshold",
+        default,
+        with = "human_duration",
         skip_serializing_if = "Option::is_none"
     )]
+    pub retry_interval: Option<Duration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub create_token_retry_threshold: Option<u64>,
     #[serde(
-        rename = "createTokenLockDecay",
+        default,
+        with = "human_duration",
         skip_serializing_if = "Option::is_none"
     )]
-    pub create_token_lock_decay: Option<chrono::Duration>,
+    pub create_token_lock_decay: Option<Duration>,
+    /// Alias used by core commands when no account, role or alias is provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_alias: Option<String>,
+    /// Region used by core and batch commands when no region is provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Cluster used by the eks command when no cluster is provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eks_cluster: Option<String>,
+    /// Regions batch exec --all-regions runs in instead of every region of the partition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_regions: Option<Vec<String>>,
+    /// Roles batch commands pick with --role auto, most preferred first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_preference: Option<Vec<String>>,
+}
+
+/// Layout of the config.json written by versions before the TOML config was introduced
+#[derive(Debug, Deserialize)]
+struct LegacyJsonConfig {
+    #[serde(rename = "startURL")]
+    start_url: String,
+    #[serde(rename = "ssoRegion")]
+    sso_region: String,
+    #[serde(rename = "maxAttempts")]
+    max_attempts: Option<usize>,
+    #[serde(rename = "initialDelay")]
+    initial_delay: Option<Duration>,
+    #[serde(rename = "retryInterval")]
+    retry_interval: Option<Duration>,
+    #[serde(rename = "createTokenRetryThreshold")]
+    create_token_retry_threshold: Option<u64>,
+    #[serde(rename = "createTokenLockDecay")]
+    create_token_lock_decay: Option<chrono::Duration>,
+}
+
+impl From<LegacyJsonConfig> for AwsSsoConfig {
+    fn from(value: LegacyJsonConfig) -> Self {
+        Self {
+            version: CURRENT_CONFIG_VERSION,
+            start_url: value.start_url,
+            sso_region: value.sso_region,
+            max_attempts: value.max_attempts,
+            initial_delay: value.initial_delay,
+            retry_interval: value.retry_interval,
+            create_token_retry_threshold: value.create_token_retry_threshold,
+            // Negative decays were never meaningful, treat them as disabled
+            create_token_lock_decay: value
+                .create_token_lock_decay
+                .map(|d| d.to_std().unwrap_or(Duration::ZERO)),
+            default_alias: None,
+            region: None,
+            eks_cluster: None,
+            batch_regions: None,
+            role_preference: None,
+        }
+    }
 }
 
 impl AwsSsoConfig {
-    fn load_config_from_reader<R: Read>(reader: R) -> Result<Self> {
-        Ok(serde_json::from_reader::<R, AwsSsoConfig>(reader)?)
+    pub fn new(start_url: String, sso_region: String) -> Self {
+        Self {
+            version: CURRENT_CONFIG_VERSION,
+            start_url,
+            sso_region,
+            max_attempts: None,
+            initial_delay: None,
+            retry_interval: None,
+            create_token_retry_threshold: None,
+            create_token_lock_decay: None,
+            default_alias: None,
+            region: None,
+            eks_cluster: None,
+            batch_regions: None,
+            role_preference: None,
+        }
+    }
+
+    pub fn from_toml_str(content: &str, config_path: &Path) -> Result<Self> {
+        let config = toml::from_str::<AwsSsoConfig>(content)
+            .map_err(|err| Error::InvalidConfig(config_path.to_path_buf(), err))?;
+        config.migrate()
+    }
+
+    /// Upgrades configs written with an older schema version to the current version.
+    /// Version 1 is the first TOML schema, migrations for later versions are chained here.
+    fn migrate(mut self) -> Result<Self> {
+        if self.version == 0 || self.version > CURRENT_CONFIG_VERSION {
+            return Err(Error::UnsupportedVersion(self.version));
+        }
+        self.version = CURRENT_CONFIG_VERSION;
+        Ok(self)
+    }
+
+    fn load_legacy_config(legacy_path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(legacy_path)
+            .map_err(|err| Error::ConfigNotFound(legacy_path.to_path_buf(), err))?;
+        let legacy = serde_json::from_str::<LegacyJsonConfig>(&content)
+            .map_err(|err| Error::InvalidLegacyConfig(legacy_path.to_path_buf(), err))?;
+        Ok(AwsSsoConfig::from(legacy))
+    }
+
+    /// Loads config.toml from the config directory. When only a legacy config.json exists,
+    /// it is converted to config.toml and kept as config.json.bak.
+    pub fn load_config(config_dir: &Path) -> Result<Self> {
+        let config_path = config_dir.join(CONFIG_FILE_NAME);
+        let legacy_path = config_dir.join(LEGACY_CONFIG_FILE_NAME);
+
+        if !config_path.exists() && legacy_path.exists() {
+            let config = AwsSsoConfig::load_legacy_config(&legacy_path)?;
+            config.save_config(config_dir)?;
+            let backup_path = legacy_path.with_extension("json.bak");
+            fs::rename(&legacy_path, &backup_path)
+                .map_err(|err| Error::Write(backup_path.clone(), err))?;
+            eprintln!(
+                "INFO: Migrated {} to {}, previous config kept at {}",
+                legacy_path.display(),
+                config_path.display(),
+                backup_path.display()
+            );
+            return Ok(config);
+        }
+
+        let content = fs::read_to_string(&config_path)
+            .map_err(|err| Error::ConfigNotFound(config_path.clone(), err))?;
+        AwsSsoConfig::from_toml_str(&content, &config_path)
+    }
+
+    /// Returns the configured value of the key, None if it is not set.
+    pub fn get_value(&self, key: &str) -> Result<Option<String>> {
+        let format_duration = |d: &Duration| humantime::format_duration(*d).to_string();
+        Ok(match key {
+            "version" => Some(self.version.to_string()),
+            "start_url" => Some(self.start_url.clone()),
+            "sso_region" => Some(self.sso_region.clone()),
+            "max_attempts" => self.max_attempts.map(|v| v.to_string()),
+            "initial_delay" => self.initial_delay.as_ref().map(format_duration),
+            "retry_interval" => self.retry_interval.as_ref().map(format_duration),
+            "create_token_retry_threshold" => {
+                self.create_token_retry_threshold.map(|v| v.to_string())
+            }
+            "create_token_lock_decay" => self.create_token_lock_decay.as_ref().map(format_duration),
+            "default_alias" => self.default_alias.clone(),
+            "region" => self.region.clone(),
+            "eks_cluster" => self.eks_cluster.clone(),
+            "batch_regions" => self.batch_regions.as_ref().map(|regions| regions.join(",")),
+            "role_preference" => self.role_preference.as_ref().map(|roles| roles.join(",")),
+            _ => return Err(Error::UnknownKey(key.to_string())),
+        })
+    }
+
+    /// Sets the key from its string representation, None resets optional keys to their default.
+    pub fn set_value(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        fn parse<T: std::str::FromStr>(key: &str, value: Option<&str>) -> Result<Option<T>>
+        where
+            T::Err: std::fmt::Display,
+        {
+            value
+                .map(|v| {
+                    v.parse::<T>().map_err(|err| Error::InvalidValue {
+                        key: key.to_string(),
+                        value: v.to_string(),
+                        reason: err.to_string(),
+                    })
+                })
+                .transpose()
+        }
+        fn parse_duration(key: &str, value: Option<&str>) -> Result<Option<Duration>> {
+            parse::<humantime::Duration>(key, value).map(|v| v.map(Into::into))
+        }
+        // Comma separated lists, empty entries are dropped
+        // Repeated items are dropped, keeping the first occurrence
+        fn parse_list(value: Option<&str>) -> Option<Vec<String>> {
+            value.map(|v| {
+                let mut items: Vec<String> = Vec::new();
+                for item in v.split(',').map(str::trim) {
+                    if !item.is_empty() && !items.iter().any(|existing| existing == item) {
+                        items.push(item.to_string());
+                    }
+                }
+                items
+            })
+        }
+        let required = |value: Option<&str>| {
+            value
+                .map(ToString::to_string)
+                .ok_or_else(|| Error::InvalidValue {
+                    key: key.to_string(),
+                    value: String::new(),
+                    reason: "key is required and cannot be unset".to_string(),
+                })
+        };
+
+        match key {
+            "version" => return Err(Error::ReadOnlyKey(key.to_string())),
+            "start_url" => self.start_url = required(value)?,
+            "sso_region" => self.sso_region = required(value)?,
+            "max_attempts" => self.max_attempts = parse(key, value)?,
+            "initial_delay" => self.initial_delay = parse_duration(key, value)?,
+            "retry_interval" => self.retry_interval = parse_duration(key, value)?,
+            "create_token_retry_threshold" => {
+                self.create_token_retry_threshold = parse(key, value)?
+            }
+            "create_token_lock_decay" => self.create_token_lock_decay = parse_duration(key, value)?,
+            "default_alias" => self.default_alias = value.map(ToString::to_string),
+            "region" => self.region = value.map(ToString::to_string),
+            "eks_cluster" => self.eks_cluster = value.map(ToString::to_string),
+            "batch_regions" => {
+                self.batch_regions = parse_list(value)
+                    .map(|regions| {
+                        regions
+                            .into_iter()
+                            .map(|region| {
+                                region::validate_region(&region)
+                                    .map(|_| region.clone())
+                                    .map_err(|reason| Error::InvalidValue {
+                                        key: key.to_string(),
+                                        value: region,
+                                        reason,
+                                    })
+                            })
+                            .collect::<Result<Vec<_>>>()
+                    })
+                    .transpose()?
+            }
+            "role_preference" => self.role_preference = parse_list(value),
+            _ => return Err(Error::UnknownKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Finds the closest project file walking up from the directory.
+    pub fn find_project_config(start_dir: &Path) -> Option<PathBuf> {
+        start_dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    fn apply_project_config(&mut self, project_path: &Path) -> Result<Vec<&'static str>> {
+        let content = fs::read_to_string(project_path)
+            .map_err(|err| Error::ConfigNotFound(project_path.to_path_buf(), err))?;
+        let table = toml::from_str::<toml::Table>(&content)
+            .map_err(|err| Error::InvalidConfig(project_path.to_path_buf(), err))?;
+        let mut applied = Vec::with_capacity(table.len());
+        for (key, value) in table {
+            let key = PROJECT_CONFIG_KEYS
+                .into_iter()
+                .find(|k| *k == key)
+                .ok_or_else(|| Error::UnsupportedProjectKey(project_path.to_path_buf(), key))?;
+            let value = match value {
+                toml::Value::String(s) => s,
+                // Lists such as batch_regions use their comma separated form
+                toml::Value::Array(values) => values
+                    .iter()
+                    .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                other => other.to_string(),
+            };
+            self.set_value(key, Some(&value))?;
+            applied.push(key);
+        }
+        Ok(applied)
+    }
+
+    /// Loads the effective config, later layers override earlier ones:
+    /// defaults < config.toml < project .aws-auth.toml < AWS_AUTH_* environment variables.
+    /// CLI flags are applied on top by the commands. Returns the source of each configured key.
+    pub fn load_effective_config_with_sources(
+        config_dir: &Path,
+    ) -> Result<(Self, BTreeMap<&'static str, ConfigSource>)> {
+        let mut config = AwsSsoConfig::load_config(config_dir)?;
+        let mut sources = BTreeMap::new();
+        for key in CONFIG_KEYS {
+            if config.get_value(key)?.is_some() {
+                sources.insert(
+                    key,
+                    ConfigSource::ConfigFile(config_dir.join(CONFIG_FILE_NAME)),
+                );
+            }
+        }
+
+        let project_path = env::current_dir()
+            .ok()
+            .and_then(|cwd| AwsSsoConfig::find_project_config(&cwd));
+        if let Some(project_path) = project_path {
+            for key in config.apply_project_config(&project_path)? {
+                sources.insert(key, ConfigSource::ProjectFile(project_path.clone()));
+            }
+        }
+
+        for key in CONFIG_KEYS.into_iter().filter(|key| *key != "version") {
+            let var = format!("{CONFIG_ENV_PREFIX}{}", key.to_uppercase());
+            if let Ok(value) = env::var(&var) {
+                config
+                    .set_value(key, Some(&value))
+                    .map_err(|err| Error::InvalidEnv(var.clone(), Box::new(err)))?;
+                sources.insert(key, ConfigSource::Env(var));
+            }
+        }
+        Ok((config, sources))
+    }
+
+    /// Splits the configured region by precedence: the region pinned by the project
+    /// .aws-auth.toml or AWS_AUTH_REGION precedes AWS_REGION, the config.toml region is a
+    /// default used after the AWS variables. Returns (pinned, default).
+    pub fn configured_regions(
+        &self,
+        sources: &BTreeMap<&'static str, ConfigSource>,
+    ) -> (Option<&str>, Option<&str>) {
+        let region = self.region.as_deref();
+        match sources.get("region") {
+            Some(ConfigSource::ProjectFile(_) | ConfigSource::Env(_)) => (region, None),
+            _ => (None, region),
+        }
+    }
+
+    pub fn load_effective_config(config_dir: &Path) -> Result<Self> {
+        Ok(AwsSsoConfig::load_effective_config_with_sources(config_dir)?.0)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String> {
+        Ok(toml::to_string(self)?)
+    }
+
+    pub fn save_config(&self, config_dir: &Path) -> Result<()> {
+        let config_path = config_dir.join(CONFIG_FILE_NAME);
+        fs::write(&config_path, self.to_toml_string()?)
+            .map_err(|err| Error::Write(config_path, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_config_conversion() {
+        let legacy = r#"{
+            "startURL": "https://my-company.awsapps.com/start",
+            "ssoRegion": "eu-west-1",
+            "initialDelay": {"secs": 10, "nanos": 0},
+            "createTokenLockDecay": [7200, 0]
+        }"#;
+        let config = AwsSsoConfig::from(serde_json::from_str::<LegacyJsonConfig>(legacy).unwrap());
+        let toml = config.to_toml_string().unwrap();
+        assert!(toml.contains("initial_delay = \"10s\""));
+        assert!(toml.contains("create_token_lock_decay = \"2h\""));
+
+        let parsed = AwsSsoConfig::from_toml_str(&toml, Path::new(CONFIG_FILE_NAME)).unwrap();
+        assert_eq!(parsed.version, CURRENT_CONFIG_VERSION);
+        assert_eq!(parsed.sso_region, "eu-west-1");
+        assert_eq!(parsed.initial_delay, Some(Duration::from_secs(10)));
     }
 
-    pub fn load_config(config_path: &Path) -> Result<Self> {
-        let config_file = File::open(config_path)
-            .map_err(|err| Error::ConfigNotFound(config_path.to_path_buf(), err))?;
-        AwsSsoConfig::load_config_from_reader(config_file)
+    #[test]
+    fn test_invalid_config_points_at_key() {
+        let content = "start_url = \"https://x.awsapps.com/start\"\nsso_region = \"eu-west-1\"\nretry_interval = \"5 parsecs\"\n";
+        let err = AwsSsoConfig::from_toml_str(content, Path::new(CONFIG_FILE_NAME)).unwrap_err();
+        assert!(err.to_string().contains("retry_interval"));
+
+        let content = "version = 99\nstart_url = \"u\"\nsso_region = \"r\"\n";
+        let err = AwsSsoConfig::from_toml_str(content, Path::new(CONFIG_FILE_NAME)).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedVersion(99)));
+    }
+
+    #[test]
+    fn test_batch_regions_drop_repeated_regions() {
+        let mut config = AwsSsoConfig::new(
+            "https://x.awsapps.com/start".to_string(),
+            "eu-west-1".to_string(),
+        );
+        config
+            .set_value("batch_regions", Some("eu-west-1, us-east-1,eu-west-1"))
+            .unwrap();
+        assert_eq!(
+            config.batch_regions,
+            Some(vec!["eu-west-1".to_string(), "us-east-1".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_project_config_overrides_targeting_keys() {
+        let root = env::temp_dir().join(format!("aws-auth-project-{}", std::process::id()));
+        let nested = root.join("infra").join("prod");
+        fs::create_dir_all(&nested).unwrap();
+        let project_path = root.join(PROJECT_CONFIG_FILE_NAME);
+        fs::write(
+            &project_path,
+            "default_alias = \"prod\"\nregion = \"us-east-1\"\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            AwsSsoConfig::find_project_config(&nested),
+            Some(project_path.clone())
+        );
+        let mut config = AwsSsoConfig::new(
+            "https://x.awsapps.com/start".to_string(),
+            "eu-west-1".to_string(),
+        );
+        let applied = config.apply_project_config(&project_path).unwrap();
+        assert_eq!(applied, vec!["default_alias", "region"]);
+        assert_eq!(config.default_alias.as_deref(), Some("prod"));
+        assert_eq!(config.region.as_deref(), Some("us-east-1"));
+
+        fs::write(
+            &project_path,
+            "start_url = \"https://evil.awsapps.com/start\"\n",
+        )
+        .unwrap();
+        let err = config.apply_project_config(&project_path).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedProjectKey(_, key) if key == "start_url"));
+
+        fs::write(&project_path, "role_preference = [\"Admin\"]\n").unwrap();
+        let err = config.apply_project_config(&project_path).unwrap_err();
+        assert!(matches!(err, Error::UnsupportedProjectKey(_, key) if key == "role_preference"));
+        fs::remove_dir_all(&root).unwrap();
     }
 }
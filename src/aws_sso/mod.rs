@@ -7,6 +7,11 @@ use std::path::Path;
 
 use crate::utils::lock::DecayingJsonCounterLockProvider;
 use auth::AuthManager;
+pub use auth::{
+    SsoRequestLimits, DEFAULT_CREATE_TOKEN_INITIAL_DELAY, DEFAULT_CREATE_TOKEN_MAX_ATTEMPTS,
+    DEFAULT_CREATE_TOKEN_RETRY_INTERVAL, DEFAULT_SSO_REQUEST_CONCURRENCY,
+    DEFAULT_SSO_REQUEST_MAX_RETRIES, DEFAULT_SSO_REQUEST_RATE,
+};
 use aws_config::Region;
 use cache::{mono_json::MonoJsonCacheManager, CacheRefMut};
 use chrono::Duration;
@@ -18,6 +23,7 @@ pub type LockProvider = DecayingJsonCounterLockProvider;
 pub type LockProviderError = std::io::Error;
 pub type AwsSsoManager<'a> = AuthManager<'a, CacheManager, LockProvider>;
 pub type AwsSsoManagerError = auth::Error<CacheManagerError, LockProviderError>;
+pub type AwsSsoConfigError = config::Error;
 
 pub const DEFAULT_CREATE_TOKEN_LOCK_THRESHOLD: u64 = 5;
 pub const DEFAULT_CREATE_TOKEN_LOCK_DECAY: chrono::Duration = chrono::Duration::seconds(2 * 3600);
@@ -26,9 +32,8 @@ fn build_aws_sso_manager<'a>(
     cache_manager: impl Into<CacheRefMut<'a, CacheManager>>,
     config_dir: &Path,
     handle_cache: bool,
-) -> AwsSsoManager<'a> {
-    let config =
-        AwsSsoConfig::load_config(&config_dir.join("config.json")).expect("Config should be valid");
+) -> Result<AwsSsoManager<'a>, AwsSsoConfigError> {
+    let config = AwsSsoConfig::load_effective_config(config_dir)?;
     let initial_delay = config
         .initial_delay
         .map(|d| Duration::from_std(d).expect("Config should be valid"));
@@ -38,8 +43,8 @@ fn build_aws_sso_manager<'a>(
         .map(|d| Duration::from_std(d).expect("Config should be valid"));
 
     let create_token_lock_decay = match config.create_token_lock_decay {
-        Some(td) if td.num_seconds() == 0 => None,
-        Some(td) => Some(td),
+        Some(td) if td.is_zero() => None,
+        Some(td) => Some(Duration::from_std(td).expect("Config should be valid")),
         None => Some(DEFAULT_CREATE_TOKEN_LOCK_DECAY),
     };
 
@@ -56,20 +61,23 @@ fn build_aws_sso_manager<'a>(
             )
         });
 
-    AwsSsoManager::new(
+    Ok(AwsSsoManager::new(
         cache_manager,
         config.start_url,
-        Region::new(config.sso_reigon),
+        Region::new(config.sso_region),
         initial_delay,
         config.max_attempts,
         retry_interval,
         None,
         handle_cache,
         lock_provider,
-    )
+    ))
 }
 
-pub fn build_sso_mgr_cached<'a>(config_dir: &Path, cache_dir: Option<&Path>) -> AwsSsoManager<'a> {
+pub fn build_sso_mgr_cached<'a>(
+    config_dir: &Path,
+    cache_dir: Option<&Path>,
+) -> Result<AwsSsoManager<'a>, AwsSsoConfigError> {
     let cache_manager = MonoJsonCacheManager::new(cache_dir.unwrap_or(config_dir));
     build_aws_sso_manager(cache_manager, config_dir, true)
 }
@@ -77,6 +85,6 @@ pub fn build_sso_mgr_cached<'a>(config_dir: &Path, cache_dir: Option<&Path>) ->
 pub fn build_sso_mgr_manual<'a>(
     cache_manager: &'a mut CacheManager,
     config_dir: &Path,
-) -> AwsSsoManager<'a> {
+) -> Result<AwsSsoManager<'a>, AwsSsoConfigError> {
     build_aws_sso_manager(cache_manager, config_dir, false)
 }
@@ -1,3 +1,12 @@
+use crate::aws_sso::config::CONFIG_KEYS;
+use crate::aws_sso::{
+    DEFAULT_SSO_REQUEST_CONCURRENCY, DEFAULT_SSO_REQUEST_MAX_RETRIES, DEFAULT_SSO_REQUEST_RATE,
+};
+use crate::commands::core::eks::{
+    DEFAULT_EXEC_CREDENTIALS_API_VERSION, SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS,
+};
+use crate::utils::region;
+use clap::builder::PossibleValuesParser;
 use clap::{Args, Parser, Subcommand};
 use std::path::PathBuf;
 
@@ -42,6 +51,24 @@ impl std::fmt::Display for OutputFormat {
     }
 }
 
+/// When to colour terminal output
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum ColorMode {
+    /// Colour when stdout is a terminal and NO_COLOR is not set
+    Auto,
+    /// Always colour
+    Always,
+    /// Never colour
+    Never,
+}
+
+/// How batch commands pick the role of each account without explicit roles
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum RoleStrategy {
+    /// First role of role_preference from config the account offers
+    Auto,
+}
+
 /// Defines output format options for eval command results
 #[derive(clap::ValueEnum, Clone, Debug)]
 pub enum EvalOutputFormat {
@@ -60,7 +87,88 @@ impl std::fmt::Display for EvalOutputFormat {
     }
 }
 
-fn validate_account_id(s: &str) -> Result<String, String> {
+/// Shells and environment file formats supported by eval output
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    /// POSIX export statements
+    Bash,
+    /// POSIX export statements
+    Zsh,
+    /// fish set -gx statements
+    Fish,
+    /// PowerShell $env: assignments
+    Powershell,
+    /// Nushell $env assignments
+    Nu,
+    /// Windows cmd.exe set statements
+    Cmd,
+    /// KEY="value" lines for .env files
+    Dotenv,
+    /// KEY=value lines to append to $GITHUB_ENV
+    GithubActions,
+}
+
+impl std::fmt::Display for Shell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Shell::Bash => write!(f, "bash"),
+            Shell::Zsh => write!(f, "zsh"),
+            Shell::Fish => write!(f, "fish"),
+            Shell::Powershell => write!(f, "powershell"),
+            Shell::Nu => write!(f, "nu"),
+            Shell::Cmd => write!(f, "cmd"),
+            Shell::Dotenv => write!(f, "dotenv"),
+            Shell::GithubActions => write!(f, "github-actions"),
+        }
+    }
+}
+
+/// Defines file formats for alias export and import
+#[derive(clap::ValueEnum, Clone, Debug)]
+pub enum AliasDataFormat {
+    /// JSON object keyed by alias, same layout as aliases.json
+    Json,
+    /// TOML table per alias
+    Toml,
+    /// YAML mapping keyed by alias
+    Yaml,
+    /// CSV with alias, accountId and role columns
+    Csv,
+}
+
+impl std::fmt::Display for AliasDataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AliasDataFormat::Json => write!(f, "json"),
+            AliasDataFormat::Toml => write!(f, "toml"),
+            AliasDataFormat::Yaml => write!(f, "yaml"),
+            AliasDataFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// Defines how imported aliases are combined with existing aliases
+#[derive(clap::ValueEnum, Clone, Debug)]
+pub enum AliasImportStrategy {
+    /// Add new aliases and overwrite existing aliases with the imported values
+    Merge,
+    /// Remove all personal aliases before importing
+    Replace,
+    /// Add new aliases and keep existing aliases untouched
+    SkipExisting,
+}
+
+impl std::fmt::Display for AliasImportStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AliasImportStrategy::Merge => write!(f, "merge"),
+            AliasImportStrategy::Replace => write!(f, "replace"),
+            AliasImportStrategy::SkipExisting => write!(f, "skip-existing"),
+        }
+    }
+}
+
+pub fn validate_account_id(s: &str) -> Result<String, String> {
     if s.len() != 12 {
         return Err(format!(
             "AWS Account ID must be exactly 12 digits, got {}",
@@ -73,8 +181,12 @@ fn validate_account_id(s: &str) -> Result<String, String> {
     Ok(s.to_string())
 }
 
+pub fn validate_region(s: &str) -> Result<String, String> {
+    region::validate_region(s).map(|_| s.to_string())
+}
+
 #[derive(Args, Clone)]
-#[group(required = true, multiple = true)]
+#[group(required = false, multiple = true)]
 pub struct AssumeInput {
     /// AWS Account ID to authenticate against (must be 12 digits)
     #[arg(short = ARG_SHORT_ACCOUNT, long, requires="role", conflicts_with="alias", value_parser=validate_account_id)]
@@ -86,6 +198,7 @@ pub struct AssumeInput {
 
     /// Predefined alias for an account and role combination
     /// Use instead of specifying account and role separately
+    /// Default: default_alias from config, .aws-auth.toml or AWS_AUTH_DEFAULT_ALIAS
     #[arg(short = ARG_SHORT_ALIAS, long, conflicts_with="account", conflicts_with="role")]
     pub alias: Option<String>,
 }
@@ -109,6 +222,12 @@ pub struct CommonArgs {
     #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
     pub config_dir: Option<PathBuf>,
 
+    /// Shared, read-only team alias catalogue (json, toml or yaml)
+    /// Personal aliases take precedence over team aliases
+    /// Can be set via AWS_AUTH_TEAM_ALIASES environment variable
+    #[arg(long, env = "AWS_AUTH_TEAM_ALIASES")]
+    pub team_aliases: Option<PathBuf>,
+
     /// Force new credential retrieval instead of using cached credentials
     /// Default: false (use cached credentials when available)
     #[arg(short = ARG_SHORT_IGNORE_CACHE, long, default_value_t = false)]
@@ -120,9 +239,11 @@ pub struct CommonArgs {
     pub refresh_sts_token: bool,
 
     /// AWS region to use for operations
-    /// Default: eu-west-2
-    #[arg(short = ARG_SHORT_REGION, long, default_value_t=String::from("eu-west-2"))]
-    pub region: String,
+    /// Default: first of the alias region, region from .aws-auth.toml or AWS_AUTH_REGION,
+    /// AWS_REGION, AWS_DEFAULT_REGION, the profile region in ~/.aws/config,
+    /// region from config and the SSO region
+    #[arg(short = ARG_SHORT_REGION, long, value_parser = validate_region)]
+    pub region: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -131,13 +252,17 @@ pub enum Commands {
     ///
     /// Creates or updates the AWS SSO configuration used by aws-auth.
     /// Stores settings in the specified (or default) configuration directory.
+    /// Prompts for missing values when run from a terminal, then offers to log in
+    /// to verify the settings and to create aliases for the available account roles.
     /// Default location: $HOME/.aws-auth
     Init {
         /// SSO start URL for AWS Identity Center (e.g., https://my-company.awsapps.com/start)
+        /// The organisation name alone (e.g., my-company) is expanded to the full URL
         #[arg(short, long)]
         sso_start_url: Option<String>,
 
         /// AWS region where the SSO service is hosted (e.g., us-east-1)
+        /// Default: guessed from the start URL or AWS_REGION, prompted for when interactive
         #[arg(short = 'r', long)]
         sso_region: Option<String>,
 
@@ -194,11 +319,36 @@ pub enum Commands {
         /// Default: 7200 (2 hour)
         #[arg(short = 'D', long)]
         create_token_lock_decay_seconds: Option<u64>,
+
+        /// Never prompt, fail when required values are missing
+        /// Default: false (prompt when attached to a terminal)
+        #[arg(long, default_value_t = false)]
+        non_interactive: bool,
+
+        /// Log in after saving the configuration to verify it works
+        /// Default: false (asked when interactive)
+        #[arg(short = 'L', long, default_value_t = false)]
+        login: bool,
+
+        /// Log in and create an alias for every available account role
+        /// Existing aliases are kept
+        /// Default: false (asked after logging in when interactive)
+        #[arg(long, default_value_t = false)]
+        import_aliases: bool,
     },
 
     #[clap(flatten)]
     Core(CoreCommands),
 
+    /// Manage aws-auth configuration
+    ///
+    /// Show, query, update, validate or edit the settings stored in config.toml
+    /// without re-running init.
+    Config {
+        #[clap(subcommand)]
+        subcommand: Config,
+    },
+
     /// Manage AWS account aliases
     ///
     /// Create, update, remove, or list aliases that map to account ID and role combinations
@@ -258,23 +408,21 @@ pub enum CoreCommands {
     ///
     /// Outputs kubectl authentication objects for use with EKS clusters.
     /// Enables kubectl to authenticate via AWS IAM credentials.
+    #[command(args_conflicts_with_subcommands = true)]
     Eks {
+        #[command(subcommand)]
+        command: Option<Box<EksCommands>>,
+
         #[clap(flatten)]
         common: CommonArgs,
 
         /// Name of the EKS cluster to generate authentication for
+        /// Default: eks_cluster from config, .aws-auth.toml or AWS_AUTH_EKS_CLUSTER
         #[arg(short = ARG_SHORT_CLUSTER, long)]
-        cluster: String,
-
-        /// Custom directory for storing EKS authentication tokens
-        /// Default: <Value specified for config-dir>/eks
-        #[arg(long)]
-        eks_cache_dir: Option<PathBuf>,
+        cluster: Option<String>,
 
-        /// Token expiration time in seconds
-        /// Default: 900 seconds (15 minutes)
-        #[arg(long)]
-        eks_expiry_seconds: Option<usize>,
+        #[clap(flatten)]
+        token: EksTokenArgs,
     },
 
     /// Output AWS environment variables for credential access
@@ -289,6 +437,82 @@ pub enum CoreCommands {
         /// Output format
         #[arg(long, short=ARG_SHORT_EVAL_OUTPUT, default_value_t=EvalOutputFormat::Eval)]
         output: EvalOutputFormat,
+
+        /// Shell syntax used by the eval output
+        /// Default: detected from $SHELL (powershell or cmd on windows), otherwise bash
+        #[arg(long, short = 's')]
+        shell: Option<Shell>,
+
+        /// Print commands clearing the variables set by aws-auth instead of credentials
+        /// No account, role or alias is required
+        /// Default: false
+        #[arg(long, short = 'u', default_value_t = false)]
+        unset: bool,
+    },
+
+    /// Open the AWS Management Console for an account and role
+    ///
+    /// Exchanges the role credentials for a console sign-in token through the
+    /// federation endpoint and opens the console in the default browser.
+    Console {
+        #[clap(flatten)]
+        common: CommonArgs,
+
+        /// Console URL to open after signing in
+        /// Default: console home page of the region
+        #[arg(short = 'd', long, conflicts_with = "service")]
+        destination: Option<String>,
+
+        /// Console service to open in the region (e.g., s3, ec2, cloudwatch)
+        #[arg(short = 's', long)]
+        service: Option<String>,
+
+        /// Console session duration in seconds (900 to 43200)
+        /// Default: determined by the federation endpoint
+        #[arg(long, value_parser = clap::value_parser!(u64).range(900..=43200))]
+        session_duration_seconds: Option<u64>,
+
+        /// Federation endpoint used to get the sign-in token
+        /// Can be set via AWS_AUTH_FEDERATION_ENDPOINT environment variable
+        /// Default: sign-in endpoint of the region's partition
+        #[arg(long, env = "AWS_AUTH_FEDERATION_ENDPOINT")]
+        federation_endpoint: Option<String>,
+
+        /// Print the sign-in URL instead of opening the browser
+        /// Default: false
+        #[arg(short = 'P', long, default_value_t = false)]
+        print: bool,
+    },
+
+    /// Write credentials into a profile of the shared credentials file
+    ///
+    /// Stores aws_access_key_id, aws_secret_access_key, aws_session_token and
+    /// aws_expiration in an INI profile for tools that only read static profiles.
+    /// Comments and other profiles in the file are preserved.
+    WriteProfile {
+        #[clap(flatten)]
+        common: CommonArgs,
+
+        /// Profile to write the credentials to
+        /// Default: name of the alias, required when using account and role
+        #[arg(short = 'p', long)]
+        profile: Option<String>,
+
+        /// Credentials file to update
+        /// Can be set via AWS_SHARED_CREDENTIALS_FILE environment variable
+        /// Default: ~/.aws/credentials
+        #[arg(long, env = "AWS_SHARED_CREDENTIALS_FILE")]
+        credentials_file: Option<PathBuf>,
+
+        /// Keep running and rewrite the profile before the credentials expire
+        /// Default: false (write once and exit)
+        #[arg(short = 'w', long, default_value_t = false)]
+        watch: bool,
+
+        /// Seconds before expiry at which watch mode refreshes the credentials
+        /// Default: 300 seconds (5 minutes)
+        #[arg(long, default_value_t = 300)]
+        refresh_before_seconds: u64,
     },
 
     /// Execute a command with AWS credentials
@@ -305,14 +529,309 @@ pub enum CoreCommands {
         #[arg(trailing_var_arg = true)]
         arguments: Vec<String>,
     },
+
+    /// Log in to Amazon ECR registries with SSO credentials
+    Ecr {
+        #[command(subcommand)]
+        command: EcrCommands,
+    },
+}
+
+/// Docker credential helper actions, see docker-credential-helpers
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum CredentialHelperAction {
+    /// Print the credentials of the registry read from stdin
+    Get,
+    /// Ignored, credentials are never stored
+    Store,
+    /// Ignored, credentials are never stored
+    Erase,
+    /// Print an empty registry list
+    List,
+}
+
+/// ECR subcommands
+#[derive(Subcommand)]
+pub enum EcrCommands {
+    /// Log docker in to Amazon ECR
+    ///
+    /// Requests an ECR authorization token with the resolved credentials and stores it in
+    /// the docker config, or prints the password for `docker login --password-stdin`.
+    Login {
+        #[clap(flatten)]
+        common: Box<CommonArgs>,
+
+        /// Accounts of the registries to log in to
+        /// Default: the account of the assumed role
+        #[arg(short = 'g', long, value_delimiter = ',', value_parser = validate_account_id)]
+        registry_account: Vec<String>,
+
+        /// Docker config directory holding config.json
+        /// Can be set via DOCKER_CONFIG environment variable
+        /// Default: ~/.docker
+        #[arg(long, env = "DOCKER_CONFIG")]
+        docker_config: Option<PathBuf>,
+
+        /// ECR API endpoint used for GetAuthorizationToken
+        /// Default: https://api.ecr.<region>.<partition domain>
+        #[arg(long, env = "AWS_AUTH_ECR_ENDPOINT")]
+        ecr_endpoint: Option<String>,
+
+        /// Print the password instead of writing the docker config
+        /// Example: aws-auth ecr login -A dev -P | docker login --username AWS --password-stdin <registry>
+        #[arg(short = 'P', long, default_value_t = false)]
+        print: bool,
+    },
+
+    /// Docker credential helper, also used when invoked as docker-credential-aws-auth
+    ///
+    /// Maps the registry host read from stdin to the first alias of the registry account,
+    /// preferring aliases in the registry region.
+    /// Example: "credHelpers": {"123456789012.dkr.ecr.eu-west-1.amazonaws.com": "aws-auth"}
+    CredentialHelper {
+        /// Credential helper action
+        #[arg(value_enum)]
+        action: CredentialHelperAction,
+
+        /// Custom directory for AWS Auth configuration
+        /// Can be set via AWS_AUTH_CONFIG_DIR environment variable
+        /// Default: ~/.aws-auth
+        #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
+        config_dir: Option<PathBuf>,
+
+        /// Custom directory for storing SSO authentication tokens
+        /// Default: Value specified for config-dir
+        #[arg(long)]
+        sso_cache_dir: Option<PathBuf>,
+
+        /// Shared, read-only team alias catalogue (json, toml or yaml)
+        /// Can be set via AWS_AUTH_TEAM_ALIASES environment variable
+        #[arg(long, env = "AWS_AUTH_TEAM_ALIASES")]
+        team_aliases: Option<PathBuf>,
+
+        /// ECR API endpoint used for GetAuthorizationToken
+        /// Default: https://api.ecr.<region>.<partition domain>
+        #[arg(long, env = "AWS_AUTH_ECR_ENDPOINT")]
+        ecr_endpoint: Option<String>,
+    },
+}
+
+/// Name docker derives from `"credHelpers": {...: "aws-auth"}`, link the binary under this name
+pub const DOCKER_CREDENTIAL_HELPER_NAME: &str = "docker-credential-aws-auth";
+
+/// Rewrites the arguments of a docker-credential-aws-auth invocation into the
+/// equivalent `aws-auth ecr credential-helper` command line.
+pub fn credential_helper_args(args: Vec<std::ffi::OsString>) -> Vec<std::ffi::OsString> {
+    let invoked_as_helper = args.first().is_some_and(|arg0| {
+        std::path::Path::new(arg0)
+            .file_stem()
+            .is_some_and(|stem| stem == DOCKER_CREDENTIAL_HELPER_NAME)
+    });
+    if !invoked_as_helper {
+        return args;
+    }
+    ["aws-auth", "ecr", "credential-helper"]
+        .into_iter()
+        .map(std::ffi::OsString::from)
+        .chain(args.into_iter().skip(1))
+        .collect()
+}
+
+/// Token arguments shared by the EKS commands
+#[derive(Args, Clone)]
+pub struct EksTokenArgs {
+    /// Custom directory for storing EKS authentication tokens
+    /// Default: <Value specified for config-dir>/eks
+    #[arg(long)]
+    pub eks_cache_dir: Option<PathBuf>,
+
+    /// Token expiration time in seconds, capped by the STS session expiry
+    /// Default: 860 seconds (just under the 15 minutes EKS accepts)
+    #[arg(long)]
+    pub eks_expiry_seconds: Option<usize>,
+
+    /// Seconds before expiry at which a cached token is replaced by a new one
+    #[arg(long, default_value_t = 30)]
+    pub eks_refresh_buffer_seconds: u64,
+
+    /// ExecCredential apiVersion of the output
+    /// Default: apiVersion requested by kubectl through KUBERNETES_EXEC_INFO,
+    /// otherwise client.authentication.k8s.io/v1beta1
+    #[arg(long, value_parser = PossibleValuesParser::new(SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS))]
+    pub api_version: Option<String>,
+
+    /// STS endpoint the token is presigned for, e.g. a VPC endpoint
+    /// Default: regional STS endpoint of the region's partition
+    #[arg(long, env = "AWS_AUTH_STS_ENDPOINT")]
+    pub sts_endpoint: Option<String>,
+
+    /// Presign the token for the FIPS STS endpoint
+    /// Can be set via AWS_USE_FIPS_ENDPOINT environment variable
+    #[arg(long, env = "AWS_USE_FIPS_ENDPOINT", default_value_t = false)]
+    pub fips: bool,
+}
+
+/// EKS helper subcommands, `aws-auth eks` without a subcommand outputs the exec credentials
+#[derive(Subcommand)]
+pub enum EksCommands {
+    /// Add the cluster to the kubeconfig file
+    ///
+    /// Writes or merges a cluster, user and context entry into the kubeconfig file.
+    /// The user authenticates by executing `aws-auth eks` with the same identity.
+    Kubeconfig {
+        #[clap(flatten)]
+        common: Box<CommonArgs>,
+
+        /// Name of the EKS cluster to add
+        /// Default: eks_cluster from config, .aws-auth.toml or AWS_AUTH_EKS_CLUSTER
+        #[arg(short = ARG_SHORT_CLUSTER, long)]
+        cluster: Option<String>,
+
+        /// Kubeconfig file to update
+        /// Default: first path in $KUBECONFIG, otherwise ~/.kube/config
+        #[arg(short = 'k', long)]
+        kubeconfig: Option<PathBuf>,
+
+        /// Name used for the context, cluster and user entries
+        /// Placeholders: {alias}, {account}, {role}, {region}, {cluster}
+        /// {alias} falls back to the account id when no alias is used
+        #[arg(short = 'n', long, default_value = "{alias}-{cluster}")]
+        context_name: String,
+
+        /// API server endpoint, skips eks:DescribeCluster when provided with --certificate-authority-data
+        #[arg(long)]
+        server: Option<String>,
+
+        /// Base64 encoded cluster CA certificate, skips eks:DescribeCluster when provided with --server
+        #[arg(long)]
+        certificate_authority_data: Option<String>,
+
+        /// EKS API endpoint used for eks:DescribeCluster
+        /// Default: https://eks.<region>.<partition domain>, e.g. amazonaws.com.cn in China
+        #[arg(long, env = "AWS_AUTH_EKS_ENDPOINT")]
+        eks_endpoint: Option<String>,
+
+        /// Command the kubeconfig user executes to get credentials
+        #[arg(long, default_value = "aws-auth")]
+        exec_command: String,
+
+        /// ExecCredential apiVersion configured for the kubeconfig user
+        #[arg(long, default_value = DEFAULT_EXEC_CREDENTIALS_API_VERSION, value_parser = PossibleValuesParser::new(SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS))]
+        api_version: String,
+
+        /// Switch the current context to the written context
+        #[arg(short = 's', long, default_value_t = false)]
+        set_current_context: bool,
+    },
+
+    /// Fill the EKS token cache for several clusters at once
+    ///
+    /// Resolves the credentials of all targets with one SSO login, concurrently, and caches a
+    /// token per cluster so subsequent kubectl calls are cache hits.
+    /// Kubeconfig contexts use the arguments of their `aws-auth eks` exec command,
+    /// the token arguments below only apply to ALIAS:CLUSTER targets.
+    Prefetch {
+        /// Targets to prefetch in the ALIAS:CLUSTER format
+        #[arg(
+            value_name = "ALIAS:CLUSTER",
+            required_unless_present = "from_kubeconfig"
+        )]
+        targets: Vec<String>,
+
+        /// Prefetch every kubeconfig context whose user executes aws-auth eks
+        #[arg(long, default_value_t = false)]
+        from_kubeconfig: bool,
+
+        /// Kubeconfig file read by --from-kubeconfig
+        /// Default: first path in $KUBECONFIG, otherwise ~/.kube/config
+        #[arg(short = 'k', long)]
+        kubeconfig: Option<PathBuf>,
+
+        /// Custom directory for AWS Auth configuration
+        /// Can be set via AWS_AUTH_CONFIG_DIR environment variable
+        /// Default: ~/.aws-auth
+        #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
+        config_dir: Option<PathBuf>,
+
+        /// Custom directory for storing SSO authentication tokens
+        /// Default: Value specified for config-dir
+        #[arg(long)]
+        sso_cache_dir: Option<PathBuf>,
+
+        /// Shared, read-only team alias catalogue (json, toml or yaml)
+        /// Can be set via AWS_AUTH_TEAM_ALIASES environment variable
+        #[arg(long, env = "AWS_AUTH_TEAM_ALIASES")]
+        team_aliases: Option<PathBuf>,
+
+        /// Force new credential retrieval instead of using cached credentials
+        #[arg(short = ARG_SHORT_IGNORE_CACHE, long, default_value_t = false)]
+        ignore_cache: bool,
+
+        /// Force refresh of the STS tokens even if the current tokens are valid
+        #[arg(short = ARG_SHORT_REFRESH_STS_TOKEN, long, default_value_t = false)]
+        refresh_sts_token: bool,
+
+        /// AWS region of the ALIAS:CLUSTER targets
+        /// Default: first of the alias region, region from .aws-auth.toml or AWS_AUTH_REGION,
+        /// AWS_REGION, AWS_DEFAULT_REGION, the profile region in ~/.aws/config,
+        /// region from config and the SSO region
+        #[arg(short = ARG_SHORT_REGION, long, value_parser = validate_region)]
+        region: Option<String>,
+
+        /// Maximum number of concurrent credential requests
+        #[arg(short = 'p', long, default_value_t = 8)]
+        parallel: usize,
+
+        #[clap(flatten)]
+        token: EksTokenArgs,
+    },
+
+    /// Inspect EKS tokens
+    Token {
+        #[command(subcommand)]
+        command: EksTokenCommands,
+    },
+}
+
+/// Troubleshooting subcommands for EKS tokens
+#[derive(Subcommand)]
+pub enum EksTokenCommands {
+    /// Decode a token and show the presigned STS request it carries
+    ///
+    /// Prints the URL components, credential scope and remaining validity,
+    /// and warns about common mistakes like an STS endpoint and signing region mismatch.
+    Inspect {
+        /// Token (k8s-aws-v1.) or ExecCredential JSON to inspect
+        /// Default: read from stdin, also when "-"
+        token: Option<String>,
+
+        /// Output format type
+        /// Options: json, text (default: text)
+        #[arg(short = 'F', long, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
 }
 
 impl CoreCommands {
-    pub fn get_common_args(&self) -> &CommonArgs {
+    /// Returns the common arguments, None for commands that do not assume a role.
+    pub fn get_common_args(&self) -> Option<&CommonArgs> {
         match self {
-            CoreCommands::Eks { common, .. } => common,
-            CoreCommands::Eval { common, .. } => common,
-            CoreCommands::Exec { common, .. } => common,
+            CoreCommands::Eks {
+                command: Some(command),
+                ..
+            } => match command.as_ref() {
+                EksCommands::Kubeconfig { common, .. } => Some(common.as_ref()),
+                EksCommands::Prefetch { .. } | EksCommands::Token { .. } => None,
+            },
+            CoreCommands::Eks { common, .. } => Some(common),
+            CoreCommands::Eval { common, .. } => Some(common),
+            CoreCommands::Exec { common, .. } => Some(common),
+            CoreCommands::WriteProfile { common, .. } => Some(common),
+            CoreCommands::Console { common, .. } => Some(common),
+            CoreCommands::Ecr { command } => match command {
+                EcrCommands::Login { common, .. } => Some(common.as_ref()),
+                EcrCommands::CredentialHelper { .. } => None,
+            },
         }
     }
 }
@@ -341,6 +860,12 @@ pub struct AliasCommonArgs {
     /// Can be set via AWS_AUTH_CONFIG_DIR environment variable
     #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
     pub config_dir: Option<PathBuf>,
+
+    /// Shared, read-only team alias catalogue (json, toml or yaml)
+    /// Personal aliases take precedence over team aliases
+    /// Can be set via AWS_AUTH_TEAM_ALIASES environment variable
+    #[arg(long, env = "AWS_AUTH_TEAM_ALIASES")]
+    pub team_aliases: Option<PathBuf>,
 }
 
 /// Subcommands for alias management
@@ -366,6 +891,10 @@ pub enum Alias {
         #[arg(short = ARG_SHORT_ROLE, long)]
         role: String,
 
+        /// AWS region used by commands targeting this alias unless --region is provided
+        #[arg(short = ARG_SHORT_REGION, long, value_parser = validate_region)]
+        region: Option<String>,
+
         /// Replace existing alias if one exists with the same name
         /// Default: false (prevents accidental overwrites)
         #[arg(short = 'w', long, default_value_t = false)]
@@ -396,6 +925,190 @@ pub enum Alias {
         #[clap(flatten)]
         formatting: FormatCommonArgs,
     },
+
+    /// Validate aliases against AWS SSO entitlements
+    ///
+    /// Checks that the account of every alias is reachable and the role is assigned to the
+    /// signed in user. Exits with a non-zero code when any alias is invalid.
+    Check {
+        /// Common alias management arguments
+        #[clap(flatten)]
+        common: AliasCommonArgs,
+
+        /// Aliases to check
+        /// Default: all configured aliases
+        aliases: Vec<String>,
+
+        /// Custom directory for storing SSO authentication tokens
+        /// Default: Value specified for config-dir
+        #[arg(long)]
+        sso_cache_dir: Option<PathBuf>,
+
+        /// Force new credential retrieval instead of using cached credentials
+        /// Default: false (use cached credentials when available)
+        #[arg(short = ARG_SHORT_IGNORE_CACHE, long, default_value_t = false)]
+        ignore_cache: bool,
+
+        /// Optional formatting arguments for the output
+        #[clap(flatten)]
+        formatting: FormatCommonArgs,
+    },
+
+    /// Rename an alias
+    ///
+    /// Moves a personal alias to a new name, keeping its account ID and role.
+    Rename {
+        /// Common alias management arguments
+        #[clap(flatten)]
+        common: AliasCommonArgs,
+
+        /// Current name of the alias
+        alias: String,
+
+        /// New name for the alias
+        new_alias: String,
+
+        /// Replace existing alias if one exists with the new name
+        /// Default: false (prevents accidental overwrites)
+        #[arg(short = 'w', long, default_value_t = false)]
+        overwrite: bool,
+    },
+
+    /// Copy an alias
+    ///
+    /// Creates a personal alias pointing at the same account ID and role as an existing alias.
+    Copy {
+        /// Common alias management arguments
+        #[clap(flatten)]
+        common: AliasCommonArgs,
+
+        /// Name of the alias to copy
+        alias: String,
+
+        /// Name of the alias to create
+        new_alias: String,
+
+        /// Replace existing alias if one exists with the new name
+        /// Default: false (prevents accidental overwrites)
+        #[arg(short = 'w', long, default_value_t = false)]
+        overwrite: bool,
+    },
+
+    /// Export aliases to stdout
+    ///
+    /// Prints all effective aliases in a format that can be read by alias import.
+    Export {
+        /// Common alias management arguments
+        #[clap(flatten)]
+        common: AliasCommonArgs,
+
+        /// Export format
+        /// Default: json
+        #[arg(short = 'F', long, default_value_t = AliasDataFormat::Json)]
+        format: AliasDataFormat,
+    },
+
+    /// Import aliases from a file
+    ///
+    /// Reads aliases from a json, toml, yaml or csv file and stores them as personal aliases.
+    Import {
+        /// Common alias management arguments
+        #[clap(flatten)]
+        common: AliasCommonArgs,
+
+        /// File to import aliases from, use - to read from stdin
+        file: PathBuf,
+
+        /// Import format
+        /// Default: Inferred from the file extension, required when reading from stdin
+        #[arg(short = 'F', long)]
+        format: Option<AliasDataFormat>,
+
+        /// How to handle aliases that already exist
+        /// Default: merge
+        #[arg(short = 's', long, default_value_t = AliasImportStrategy::Merge)]
+        strategy: AliasImportStrategy,
+    },
+}
+
+#[derive(Args)]
+pub struct ConfigCommonArgs {
+    /// Custom directory for AWS Auth configuration
+    /// Can be set via AWS_AUTH_CONFIG_DIR environment variable
+    /// Default: ~/.aws-auth
+    #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
+    pub config_dir: Option<PathBuf>,
+}
+
+/// Subcommands for configuration management
+#[derive(Subcommand)]
+pub enum Config {
+    /// Display the effective configuration
+    ///
+    /// Shows every setting with its effective value and where it comes from.
+    /// Later sources override earlier ones: built-in defaults, config.toml,
+    /// the nearest .aws-auth.toml in the current or a parent directory, then
+    /// AWS_AUTH_<KEY> environment variables (e.g. AWS_AUTH_REGION).
+    Show {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+
+        /// Optional formatting arguments for the output
+        #[clap(flatten)]
+        formatting: FormatCommonArgs,
+    },
+
+    /// Print the effective value of a setting
+    Get {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+
+        /// Setting to print
+        #[arg(value_parser = PossibleValuesParser::new(CONFIG_KEYS))]
+        key: String,
+    },
+
+    /// Update a setting
+    ///
+    /// Durations accept human readable values such as 10s, 5m or 2h.
+    Set {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+
+        /// Setting to update
+        #[arg(value_parser = PossibleValuesParser::new(CONFIG_KEYS))]
+        key: String,
+
+        /// New value of the setting
+        value: String,
+    },
+
+    /// Reset a setting to its default
+    Unset {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+
+        /// Setting to reset
+        #[arg(value_parser = PossibleValuesParser::new(CONFIG_KEYS))]
+        key: String,
+    },
+
+    /// Validate the configuration
+    ///
+    /// Checks the start URL shape, the SSO region and that the configuration
+    /// directory is writable. Exits with a non-zero code when errors are found.
+    Validate {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+    },
+
+    /// Edit config.toml in $VISUAL or $EDITOR
+    ///
+    /// The configuration is validated when the editor exits and only saved if it is valid.
+    Edit {
+        #[clap(flatten)]
+        common: ConfigCommonArgs,
+    },
 }
 
 #[derive(Args)]
@@ -458,9 +1171,18 @@ pub struct BatchCommonArgs {
 
     /// IAM roles to attempt in priority order
     /// First successful role will be used for operations
-    #[arg(short = ARG_SHORT_ROLE, long)]
+    #[arg(short = ARG_SHORT_ROLE, long, conflicts_with_all = ["role_regex", "role"])]
     pub role_order: Option<Vec<String>>,
 
+    /// Use the first role, by name, matching the regular expression among the roles of each account
+    #[arg(long, conflicts_with = "role")]
+    pub role_regex: Option<String>,
+
+    /// Role selection strategy, auto picks roles following role_preference from config
+    /// Default: auto when role_preference is configured and no other role option is given
+    #[arg(long, value_enum)]
+    pub role: Option<RoleStrategy>,
+
     /// Target accounts by configured aliases (comma-separated list)
     #[arg(short = ARG_SHORT_ALIAS, long, value_delimiter = ',')]
     pub aliases: Option<Vec<String>>,
@@ -470,15 +1192,30 @@ pub struct BatchCommonArgs {
     pub account_filter_regex: Option<String>,
 
     /// AWS region for operations
-    /// Default: eu-west-2
-    #[arg(short = ARG_SHORT_REGION, long, default_value_t=String::from("eu-west-2"))]
-    pub region: String,
+    /// Default: first of the region from .aws-auth.toml or AWS_AUTH_REGION, AWS_REGION,
+    /// AWS_DEFAULT_REGION, the profile region in ~/.aws/config, region from config
+    /// and the SSO region
+    #[arg(short = ARG_SHORT_REGION, long, value_parser = validate_region)]
+    pub region: Option<String>,
 
     /// Number of concurrent operations to perform
     /// Default: 1 (sequential processing)
     #[arg(short = 'p', long, default_value_t = 1)]
     pub parallel: usize,
 
+    /// Number of accounts whose roles and credentials are resolved concurrently
+    /// Jobs start as soon as the credentials of their account are resolved
+    #[arg(long, default_value_t = DEFAULT_SSO_REQUEST_CONCURRENCY)]
+    pub resolve_concurrency: usize,
+
+    /// Maximum SSO requests started per second, 0 disables the limit
+    #[arg(long, default_value_t = DEFAULT_SSO_REQUEST_RATE)]
+    pub rate_limit: f64,
+
+    /// Retries of requests throttled by SSO, with jittered backoff
+    #[arg(long, default_value_t = DEFAULT_SSO_REQUEST_MAX_RETRIES)]
+    pub max_retries: u32,
+
     /// Custom directory for storing SSO authentication tokens
     /// Default: Value specified for config-dir
     #[arg(long)]
@@ -490,6 +1227,12 @@ pub struct BatchCommonArgs {
     #[arg(short = ARG_SHORT_CONFIG_DIR, long, env = "AWS_AUTH_CONFIG_DIR")]
     pub config_dir: Option<PathBuf>,
 
+    /// Shared, read-only team alias catalogue (json, toml or yaml)
+    /// Personal aliases take precedence over team aliases
+    /// Can be set via AWS_AUTH_TEAM_ALIASES environment variable
+    #[arg(long, env = "AWS_AUTH_TEAM_ALIASES")]
+    pub team_aliases: Option<PathBuf>,
+
     /// Force new credential retrieval instead of using cached credentials
     /// Default: false (use cached credentials when available)
     #[arg(short = ARG_SHORT_IGNORE_CACHE, long, default_value_t = false)]
@@ -517,22 +1260,102 @@ pub enum Batch {
         #[arg(short = 'S', long, default_value_t = false)]
         suppress_output: bool,
 
-        /// Directory to save per-account output files
+        /// Regions to run the command in, once per account and region (comma-separated list)
+        /// Default: the single region resolved like --region
+        #[arg(long, value_delimiter = ',', value_parser = validate_region, conflicts_with = "region")]
+        regions: Option<Vec<String>>,
+
+        /// Run the command in every region of batch_regions from config,
+        /// or in every region of the partition enabled by default when batch_regions is not configured
+        /// Opt-in regions, such as af-south-1 or me-south-1, are only used when listed in batch_regions
+        #[arg(long, default_value_t = false, conflicts_with_all = ["region", "regions"])]
+        all_regions: bool,
+
+        /// Directory to save per-account output files, may contain placeholders
         /// No effect if suppress_output is enabled
         #[arg(short = 'o', long)]
         output_dir: Option<PathBuf>,
 
+        /// Name of the per-account output files, written as <name>-stdout.log and <name>-stderr.log
+        /// Supports the same placeholders as the arguments
+        /// Default: {account_id}, {account_id}-{region} when running in several regions
+        #[arg(long, requires = "output_dir")]
+        output_name: Option<String>,
+
+        /// Prefix every output line with the account alias, name or ID
+        /// Lines are printed as they are written, use --group to keep accounts together
+        #[arg(long, default_value_t = false, conflicts_with_all = ["suppress_output", "output_dir"])]
+        prefix: bool,
+
+        /// Buffer the output of each account and print it contiguously once its command completed
+        #[arg(long, default_value_t = false, conflicts_with_all = ["suppress_output", "output_dir"])]
+        group: bool,
+
+        /// Colour the --prefix labels, one colour per account
+        /// Default: auto (colour when stdout is a terminal and NO_COLOR is not set)
+        #[arg(long, value_enum, default_value_t = ColorMode::Auto)]
+        color: ColorMode,
+
+        /// Format of the summary printed once every account finished, and of the --dry-run list
+        /// Default: text
+        #[arg(long, default_value_t = OutputFormat::Text)]
+        report: OutputFormat,
+
+        /// Exit successfully even when the command failed in some jobs
+        /// Default: false (exit with an error when any account failed)
+        #[arg(long, default_value_t = false)]
+        allow_failures: bool,
+
+        /// List the accounts, roles and regions the command would run in without running it
+        /// The list uses the --report format
+        #[arg(long, default_value_t = false, conflicts_with = "confirm")]
+        dry_run: bool,
+
+        /// Resolve the credentials during --dry-run, listing the role actually assumed
+        /// and leaving out accounts none of the roles can be assumed in
+        /// Default: false (list the candidate roles of each account)
+        #[arg(long, default_value_t = false, requires = "dry_run")]
+        resolve_credentials: bool,
+
+        /// Ask for confirmation, showing the number of accounts, before running the command
+        /// Requires an interactive terminal
+        #[arg(long, default_value_t = false)]
+        confirm: bool,
+
         /// Command and arguments to execute
         /// Must be provided after -- separator
-        /// Example: aws-auth batch exec -A prod-account -- aws s3 ls
+        /// Placeholders {account_id}, {account_name}, {role}, {alias} and {region} are expanded per account,
+        /// the command also receives AWS_ACCOUNT_ID, AWS_ACCOUNT_NAME and AWS_AUTH_ROLE
+        /// Example: aws-auth batch exec -A prod-account -- aws s3 ls s3://logs-{account_id}
         #[arg(trailing_var_arg = true, required = true)]
         arguments: Vec<String>,
     },
+
+    /// Write credentials of multiple AWS accounts into credentials file profiles
+    ///
+    /// Profiles are named after the alias when targeting aliases, otherwise after the account ID.
+    /// Comments and other profiles in the file are preserved.
+    WriteProfiles {
+        #[clap(flatten)]
+        batch_common: BatchCommonArgs,
+
+        /// Prefix added to every profile name
+        /// Default: no prefix
+        #[arg(long, default_value = "")]
+        profile_prefix: String,
+
+        /// Credentials file to update
+        /// Can be set via AWS_SHARED_CREDENTIALS_FILE environment variable
+        /// Default: ~/.aws/credentials
+        #[arg(long, env = "AWS_SHARED_CREDENTIALS_FILE")]
+        credentials_file: Option<PathBuf>,
+    },
 }
 impl Batch {
     pub fn get_common_args(&self) -> &BatchCommonArgs {
         match self {
             Batch::Exec { batch_common, .. } => batch_common,
+            Batch::WriteProfiles { batch_common, .. } => batch_common,
         }
     }
 }
@@ -1,8 +1,20 @@
-use crate::alias_providers::{build_alias_provider_and_load, AliasProviderError, ProvideAliases};
-use crate::cmd::Alias;
+use crate::alias_providers::{
+    build_alias_provider_and_load,
+    file_alias_provider::{AccountRole, AliasFileFormat},
+    layered_alias_provider::AliasLayer,
+    AliasProvider, AliasProviderError, ProvideAliases,
+};
+use crate::aws_sso::{build_sso_mgr_cached, AwsSsoConfigError, AwsSsoManagerError};
+use crate::cmd::{validate_account_id, Alias, AliasDataFormat, AliasImportStrategy};
+use crate::common::AssumeIdentifier;
 use crate::utils::formatters::text::TextFormatter;
 use crate::utils::formatters::TabularFormatter;
-use crate::utils::{self, formatters::json::JsonFormatter};
+use crate::utils::{self, formatters::json::JsonFormatter, region};
+use aws_sdk_sso::types::RoleInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::path::Path;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -12,52 +24,565 @@ pub enum Error {
     AliasAlreadyExists(String),
     #[error("Error formating aliases list using json output: {0}")]
     JsonFormatter(#[from] serde_json::Error),
+    #[error("Alias {0} not found")]
+    AliasNotFound(String),
+    #[error("Alias {0} is defined in the read-only {1} layer")]
+    ReadOnlyAlias(String, AliasLayer),
+    #[error("Error exporting aliases: {0}")]
+    Export(io::Error),
+    #[error("Error reading aliases to import: {0}")]
+    Import(io::Error),
+    #[error("Unable to infer import format from {0:?}, provide the format flag")]
+    UnknownImportFormat(String),
+    #[error("Invalid alias {0}: {1}")]
+    InvalidAlias(String, String),
+    #[error("Error processing csv: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error checking aliases against AWS SSO: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("{0} alias(es) failed validation")]
+    InvalidAliases(usize),
 }
 
-pub fn exec_alias(subcommand: Alias) -> Result<(), Error> {
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+/// Result of validating an alias against the SSO entitlements of the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AliasStatus {
+    Valid,
+    AccountUnreachable,
+    RoleMissing,
+}
+
+impl AliasStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            AliasStatus::Valid => "valid",
+            AliasStatus::AccountUnreachable => "account-unreachable",
+            AliasStatus::RoleMissing => "role-missing",
+        }
+    }
+}
+
+/// Roles of the account, None when SSO denies access to the account. Other errors, such as
+/// throttling, network errors or an expired session, are returned rather than marking valid
+/// aliases as broken.
+fn reachable_roles(
+    result: Result<Vec<RoleInfo>, AwsSsoManagerError>,
+) -> Result<Option<Vec<String>>, Error> {
+    match result {
+        Ok(roles) => Ok(Some(
+            roles
+                .into_iter()
+                .filter_map(|role| role.role_name)
+                .collect(),
+        )),
+        Err(err) if err.is_access_denied() => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn alias_status(account_roles: Option<&Vec<String>>, role: &str) -> AliasStatus {
+    match account_roles {
+        Some(roles) if roles.iter().any(|r| r == role) => AliasStatus::Valid,
+        Some(_) => AliasStatus::RoleMissing,
+        None => AliasStatus::AccountUnreachable,
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CsvAlias {
+    alias: String,
+    #[serde(rename = "accountId")]
+    account: String,
+    role: String,
+    #[serde(default)]
+    region: Option<String>,
+}
+
+fn infer_import_format(file: &Path) -> Option<AliasDataFormat> {
+    match file.extension().and_then(|ext| ext.to_str()) {
+        Some("csv") => Some(AliasDataFormat::Csv),
+        _ => match AliasFileFormat::from_path(file).ok()? {
+            AliasFileFormat::Json => Some(AliasDataFormat::Json),
+            AliasFileFormat::Toml => Some(AliasDataFormat::Toml),
+            AliasFileFormat::Yaml => Some(AliasDataFormat::Yaml),
+        },
+    }
+}
+
+fn serialize_aliases(
+    format: &AliasDataFormat,
+    aliases: &BTreeMap<String, AccountRole>,
+) -> Result<String, Error> {
+    let file_format = match format {
+        AliasDataFormat::Json => AliasFileFormat::Json,
+        AliasDataFormat::Toml => AliasFileFormat::Toml,
+        AliasDataFormat::Yaml => AliasFileFormat::Yaml,
+        AliasDataFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            for (alias, account_role) in aliases {
+                writer.serialize(CsvAlias {
+                    alias: alias.clone(),
+                    account: account_role.account.clone(),
+                    role: account_role.role.clone(),
+                    region: account_role.region.clone(),
+                })?;
+            }
+            let output = writer
+                .into_inner()
+                .map_err(|err| Error::Export(err.into_error()))?;
+            return String::from_utf8(output)
+                .map_err(|err| Error::Export(io::Error::new(io::ErrorKind::InvalidData, err)));
+        }
+    };
+    file_format.serialize(aliases).map_err(Error::Export)
+}
+
+fn parse_aliases(
+    format: &AliasDataFormat,
+    content: &str,
+) -> Result<BTreeMap<String, AccountRole>, Error> {
+    let aliases = match format {
+        AliasDataFormat::Json => AliasFileFormat::Json.parse(content),
+        AliasDataFormat::Toml => AliasFileFormat::Toml.parse(content),
+        AliasDataFormat::Yaml => AliasFileFormat::Yaml.parse(content),
+        AliasDataFormat::Csv => {
+            let mut aliases = BTreeMap::new();
+            for row in csv::Reader::from_reader(content.as_bytes()).deserialize::<CsvAlias>() {
+                let row = row?;
+                aliases.insert(
+                    row.alias,
+                    AccountRole {
+                        account: row.account,
+                        role: row.role,
+                        region: row.region.filter(|region| !region.is_empty()),
+                    },
+                );
+            }
+            Ok(aliases)
+        }
+    }
+    .map_err(Error::Import)?;
+
+    for (alias, account_role) in aliases.iter() {
+        validate_account_id(&account_role.account)
+            .map_err(|err| Error::InvalidAlias(alias.clone(), err))?;
+        if let Some(region) = &account_role.region {
+            region::validate_region(region)
+                .map_err(|err| Error::InvalidAlias(alias.clone(), err))?;
+        }
+    }
+    Ok(aliases)
+}
+
+/// Renames a personal alias, renaming an alias to itself changes nothing.
+fn rename_alias(
+    alias_provider: &mut AliasProvider,
+    alias: String,
+    new_alias: String,
+    overwrite: bool,
+) -> Result<(), Error> {
+    match alias_provider.get_alias_layer(&alias)? {
+        None => return Err(Error::AliasNotFound(alias)),
+        Some(AliasLayer::Personal) => {}
+        Some(layer) => return Err(Error::ReadOnlyAlias(alias, layer)),
+    }
+    if new_alias == alias {
+        return Ok(());
+    }
+    if alias_provider.get_alias(&new_alias)?.is_some() && !overwrite {
+        return Err(Error::AliasAlreadyExists(new_alias));
+    }
+    let (account, role, region) = alias_provider
+        .get_alias(&alias)?
+        .map(owned_assume_identifier)
+        .expect("Alias presence checked above");
+    alias_provider.set_alias(&new_alias, &account, &role, region.as_deref())?;
+    alias_provider.unset_alias(&alias)?;
+    Ok(())
+}
+
+/// Combines the imported aliases with the personal aliases following the strategy, written
+/// at once. Returns the number of imported aliases.
+fn import_aliases(
+    alias_provider: &mut AliasProvider,
+    imported: &BTreeMap<String, AccountRole>,
+    strategy: &AliasImportStrategy,
+) -> Result<usize, Error> {
+    let mut to_import = Vec::with_capacity(imported.len());
+    for (alias, account_role) in imported.iter() {
+        if let AliasImportStrategy::SkipExisting = strategy {
+            if alias_provider.get_alias(alias)?.is_some() {
+                continue;
+            }
+        }
+        to_import.push((
+            alias.as_str(),
+            AssumeIdentifier {
+                account: &account_role.account,
+                role: &account_role.role,
+                region: account_role.region.as_deref(),
+            },
+        ));
+    }
+    if let AliasImportStrategy::Replace = strategy {
+        alias_provider.replace_aliases(&to_import)?;
+    } else {
+        alias_provider.set_aliases(&to_import)?;
+    }
+    Ok(to_import.len())
+}
+
+fn owned_assume_identifier(ai: AssumeIdentifier<'_>) -> (String, String, Option<String>) {
+    (
+        ai.account.to_string(),
+        ai.role.to_string(),
+        ai.region.map(ToString::to_string),
+    )
+}
+
+pub async fn exec_alias(subcommand: Alias) -> Result<(), Error> {
     match subcommand {
         Alias::Set {
             common,
             alias,
             account,
             role,
+            region,
             overwrite,
         } => {
             let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
-            let mut alias_provider = build_alias_provider_and_load(&config_dir)?;
+            let mut alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
             if alias_provider.get_alias(&alias)?.is_some() && !overwrite {
                 return Err(Error::AliasAlreadyExists(alias));
             }
             alias_provider
-                .set_alias(&alias, &account, &role)
+                .set_alias(&alias, &account, &role, region.as_deref())
                 .map_err(Error::AliasProvider)?;
         }
         Alias::Unset { common, alias } => {
             let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
-            let mut alias_provider = build_alias_provider_and_load(&config_dir)?;
+            let mut alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
             alias_provider.unset_alias(&alias)?;
         }
         Alias::List { common, formatting } => {
             let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
-            let alias_provider = build_alias_provider_and_load(&config_dir)?;
-            let aliases: Vec<[&str; 3]> = alias_provider.list_aliases()?;
+            let alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            let mut aliases: Vec<[&str; 5]> = Vec::new();
+            for [alias, account, role, layer] in alias_provider.list_aliases_with_layer()? {
+                let region = alias_provider
+                    .get_alias(alias)?
+                    .and_then(|ai| ai.region)
+                    .unwrap_or("");
+                aliases.push([alias, account, role, region, layer]);
+            }
+            let omit_fields = formatting.omit_fields.iter().map(|v| v.as_str()).collect();
+
+            match formatting.output {
+                crate::cmd::OutputFormat::Json => {
+                    let formatter = JsonFormatter::new(omit_fields, formatting.no_headers);
+                    let output = formatter
+                        .format(&["alias", "accountId", "role", "region", "source"], aliases)?;
+                    println!("{}", output)
+                }
+                crate::cmd::OutputFormat::Text => {
+                    let formatter = TextFormatter::new(omit_fields, formatting.no_headers, " | ");
+                    let output = formatter
+                        .format(
+                            &["Alias", "Account Id", "Role", "Region", "Source"],
+                            aliases,
+                        )
+                        .expect("TextFormatter doesnt error. Returns result to satisfy trait");
+                    println!("{}", output)
+                }
+            }
+        }
+        Alias::Check {
+            common,
+            aliases: selected,
+            sso_cache_dir,
+            ignore_cache,
+            formatting,
+        } => {
+            let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
+            let alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            let aliases = alias_provider
+                .list_aliases()?
+                .into_iter()
+                .filter(|[alias, ..]| selected.is_empty() || selected.iter().any(|s| s == alias))
+                .collect::<Vec<_>>();
+            if let Some(missing) = selected
+                .iter()
+                .find(|s| !aliases.iter().any(|[alias, ..]| alias == s))
+            {
+                return Err(Error::AliasNotFound(missing.clone()));
+            }
+
+            // Roles available per distinct account, None if access to the account is denied
+            let mut sso_manager = build_sso_mgr_cached(&config_dir, sso_cache_dir.as_deref())?;
+            let mut account_roles: HashMap<&str, Option<Vec<String>>> = HashMap::new();
+            let mut ignore_cache = ignore_cache;
+            for [_, account, _] in aliases.iter() {
+                if account_roles.contains_key(account) {
+                    continue;
+                }
+                let roles =
+                    reachable_roles(sso_manager.list_account_roles(account, ignore_cache).await)?;
+                // Only the first call has to bypass the cache, later calls reuse the new session
+                ignore_cache = false;
+                account_roles.insert(account, roles);
+            }
+
+            let results = aliases
+                .iter()
+                .map(|[alias, account, role]| {
+                    let status =
+                        alias_status(account_roles.get(account).and_then(Option::as_ref), role);
+                    [*alias, *account, *role, status.as_str()]
+                })
+                .collect::<Vec<_>>();
+            let invalid = results
+                .iter()
+                .filter(|[.., status]| *status != AliasStatus::Valid.as_str())
+                .count();
             let omit_fields = formatting.omit_fields.iter().map(|v| v.as_str()).collect();
 
             match formatting.output {
                 crate::cmd::OutputFormat::Json => {
                     let formatter = JsonFormatter::new(omit_fields, formatting.no_headers);
-                    let output = formatter.format(&["alias", "accountId", "role"], aliases)?;
+                    let output =
+                        formatter.format(&["alias", "accountId", "role", "status"], results)?;
                     println!("{}", output)
                 }
                 crate::cmd::OutputFormat::Text => {
                     let formatter = TextFormatter::new(omit_fields, formatting.no_headers, " | ");
                     let output = formatter
-                        .format(&["Alias", "Account Id", "Role"], aliases)
+                        .format(&["Alias", "Account Id", "Role", "Status"], results)
                         .expect("TextFormatter doesnt error. Returns result to satisfy trait");
                     println!("{}", output)
                 }
             }
+
+            if invalid > 0 {
+                return Err(Error::InvalidAliases(invalid));
+            }
+        }
+        Alias::Rename {
+            common,
+            alias,
+            new_alias,
+            overwrite,
+        } => {
+            let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
+            let mut alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            rename_alias(&mut alias_provider, alias, new_alias, overwrite)?;
+        }
+        Alias::Copy {
+            common,
+            alias,
+            new_alias,
+            overwrite,
+        } => {
+            let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
+            let mut alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            if alias_provider.get_alias(&new_alias)?.is_some() && !overwrite {
+                return Err(Error::AliasAlreadyExists(new_alias));
+            }
+            let (account, role, region) = alias_provider
+                .get_alias(&alias)?
+                .map(owned_assume_identifier)
+                .ok_or(Error::AliasNotFound(alias))?;
+            alias_provider.set_alias(&new_alias, &account, &role, region.as_deref())?;
+        }
+        Alias::Export { common, format } => {
+            let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
+            let alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            let mut aliases = BTreeMap::new();
+            for [alias, account, role] in alias_provider.list_aliases()? {
+                let region = alias_provider
+                    .get_alias(alias)?
+                    .and_then(|ai| ai.region.map(ToString::to_string));
+                aliases.insert(
+                    alias.to_string(),
+                    AccountRole {
+                        account: account.to_string(),
+                        role: role.to_string(),
+                        region,
+                    },
+                );
+            }
+            println!("{}", serialize_aliases(&format, &aliases)?.trim_end());
+        }
+        Alias::Import {
+            common,
+            file,
+            format,
+            strategy,
+        } => {
+            let format = format
+                .or_else(|| infer_import_format(&file))
+                .ok_or_else(|| Error::UnknownImportFormat(file.display().to_string()))?;
+            let mut content = String::new();
+            if file.as_os_str() == "-" {
+                io::stdin()
+                    .read_to_string(&mut content)
+                    .map_err(Error::Import)?;
+            } else {
+                content = std::fs::read_to_string(&file).map_err(Error::Import)?;
+            }
+            let imported = parse_aliases(&format, &content)?;
+
+            let config_dir = utils::resolve_config_dir(common.config_dir.as_deref());
+            let mut alias_provider =
+                build_alias_provider_and_load(&config_dir, common.team_aliases.as_deref())?;
+            let imported_count = import_aliases(&mut alias_provider, &imported, &strategy)?;
+            println!(
+                "INFO: Imported {} aliases, skipped {} existing aliases",
+                imported_count,
+                imported.len() - imported_count
+            );
         }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alias_providers::{
+        file_alias_provider::FileAliasProvider, layered_alias_provider::LayeredAliasProvider,
+    };
+    use std::fs;
+
+    fn personal_provider(dir: &Path) -> AliasProvider {
+        fs::create_dir_all(dir).unwrap();
+        let mut provider =
+            LayeredAliasProvider::new(FileAliasProvider::new(dir.join("aliases.json")).unwrap());
+        provider.load_aliases().unwrap();
+        provider
+    }
+
+    #[test]
+    fn test_rename_alias_to_itself_keeps_alias() {
+        let dir = std::env::temp_dir().join(format!("aws-auth-rename-{}", std::process::id()));
+        let mut provider = personal_provider(&dir);
+        provider
+            .set_alias("dev", "123456789012", "ReadOnly", None)
+            .unwrap();
+
+        rename_alias(&mut provider, "dev".to_string(), "dev".to_string(), true).unwrap();
+        assert_eq!(personal_provider(&dir).list_aliases().unwrap().len(), 1);
+
+        rename_alias(
+            &mut provider,
+            "dev".to_string(),
+            "staging".to_string(),
+            false,
+        )
+        .unwrap();
+        let reloaded = personal_provider(&dir);
+        assert!(reloaded.get_alias("dev").unwrap().is_none());
+        assert_eq!(
+            reloaded.get_alias("staging").unwrap().unwrap().account,
+            "123456789012"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replace_import_round_trips_export() {
+        let dir = std::env::temp_dir().join(format!("aws-auth-import-{}", std::process::id()));
+        let mut provider = personal_provider(&dir);
+        provider
+            .set_alias("old", "123456789012", "Admin", None)
+            .unwrap();
+
+        let exported = BTreeMap::from([
+            (
+                "dev".to_string(),
+                AccountRole {
+                    account: "210987654321".to_string(),
+                    role: "ReadOnly".to_string(),
+                    region: Some("eu-west-1".to_string()),
+                },
+            ),
+            (
+                "prod".to_string(),
+                AccountRole {
+                    account: "111111111111".to_string(),
+                    role: "Admin".to_string(),
+                    region: None,
+                },
+            ),
+        ]);
+        let content = serialize_aliases(&AliasDataFormat::Yaml, &exported).unwrap();
+        let imported = parse_aliases(&AliasDataFormat::Yaml, &content).unwrap();
+        assert_eq!(
+            import_aliases(&mut provider, &imported, &AliasImportStrategy::Replace).unwrap(),
+            2
+        );
+
+        let reloaded = personal_provider(&dir);
+        assert_eq!(
+            reloaded.list_aliases().unwrap(),
+            vec![
+                ["dev", "210987654321", "ReadOnly"],
+                ["prod", "111111111111", "Admin"],
+            ]
+        );
+        assert_eq!(
+            reloaded.get_alias("dev").unwrap().unwrap().region,
+            Some("eu-west-1")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_alias_status_only_marks_denied_accounts_unreachable() {
+        use aws_sdk_sso::error::ErrorMetadata;
+        use aws_sdk_sso::operation::list_account_roles::ListAccountRolesError;
+        use aws_smithy_runtime_api::client::result::SdkError;
+        use aws_smithy_runtime_api::http::{Response, StatusCode};
+
+        let list_error = |status: u16, code: &str| {
+            AwsSsoManagerError::OidcListAccountRoles(SdkError::service_error(
+                ListAccountRolesError::generic(ErrorMetadata::builder().code(code).build()),
+                Response::new(StatusCode::try_from(status).unwrap(), "".into()),
+            ))
+        };
+
+        let denied = reachable_roles(Err(list_error(403, "ForbiddenException"))).unwrap();
+        assert_eq!(
+            alias_status(denied.as_ref(), "Admin"),
+            AliasStatus::AccountUnreachable
+        );
+        assert!(reachable_roles(Err(list_error(429, "TooManyRequestsException"))).is_err());
+
+        let roles =
+            reachable_roles(Ok(vec![RoleInfo::builder().role_name("Admin").build()])).unwrap();
+        assert_eq!(alias_status(roles.as_ref(), "Admin"), AliasStatus::Valid);
+        assert_eq!(
+            alias_status(roles.as_ref(), "ReadOnly"),
+            AliasStatus::RoleMissing
+        );
+        let no_roles = reachable_roles(Ok(Vec::new())).unwrap();
+        assert_eq!(
+            alias_status(no_roles.as_ref(), "Admin"),
+            AliasStatus::RoleMissing
+        );
+    }
+}
@@ -6,7 +6,7 @@ use std::process::{Command, Stdio};
 use std::sync::Arc;
 use std::thread;
 
-use crate::utils::worker::Job;
+use crate::utils::{render_placeholders, sanitize_path_component, worker::Job};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -20,14 +20,95 @@ pub enum Error {
     Thread(String),
 }
 
+// ANSI colours cycled through by account
+const PREFIX_COLORS: [&str; 6] = ["36", "33", "35", "32", "34", "31"];
+
+/// Renders the `[label] ` prefix of each label, padded to the longest label and coloured by position.
+pub fn render_prefixes(labels: &[&str], color: bool) -> Vec<String> {
+    let width = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+    labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let label = format!("[{label}]");
+            let padding = " ".repeat(width + 3 - label.len());
+            if color {
+                let code = PREFIX_COLORS[i % PREFIX_COLORS.len()];
+                format!("\x1b[{code}m{label}\x1b[0m{padding}")
+            } else {
+                format!("{label}{padding}")
+            }
+        })
+        .collect()
+}
+
 #[derive(Debug)]
 pub struct ExecJob {
+    // <account_id>/<region>
+    pub job_id: String,
     pub account_id: String,
     pub credentials: Credentials,
     pub region: Arc<String>,
     pub arguments: Arc<[String]>,
+    pub account_name: Option<String>,
+    pub role: String,
+    pub alias: Option<String>,
     pub suppress_output: bool,
+    // Both may contain placeholders, see ExecJob::placeholders
     pub output_base_path: Option<Arc<PathBuf>>,
+    pub output_name: Arc<str>,
+    // Written before every output line, including colour codes
+    pub output_prefix: Option<String>,
+    pub group_output: bool,
+}
+
+/// Writes every line prefixed, each complete line reaches the inner writer in a single
+/// write_all so lines of concurrent accounts do not mix. Flushing writes a trailing partial line.
+pub struct PrefixWriter<W: Write> {
+    prefix: String,
+    inner: W,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> PrefixWriter<W> {
+    pub fn new(prefix: String, inner: W) -> Self {
+        Self {
+            prefix,
+            inner,
+            pending: Vec::new(),
+        }
+    }
+
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        let mut prefixed = Vec::with_capacity(self.prefix.len() + line.len());
+        prefixed.extend_from_slice(self.prefix.as_bytes());
+        prefixed.extend_from_slice(line);
+        self.inner.write_all(&prefixed)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for PrefixWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line = self.pending.drain(..=end).collect::<Vec<_>>();
+            self.write_line(&line)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let mut line = std::mem::take(&mut self.pending);
+            line.push(b'\n');
+            self.write_line(&line)?;
+        }
+        self.inner.flush()
+    }
 }
 
 impl ExecJob {
@@ -35,6 +116,21 @@ impl ExecJob {
         let _ = arguments.first().ok_or(Error::MissingProgram)?;
         Ok(())
     }
+
+    /// Values of the placeholders expanded in the arguments and output paths. The account name
+    /// and alias fall back to the account ID when unknown.
+    fn placeholders(&self) -> [(&str, &str); 5] {
+        [
+            ("account_id", &self.account_id),
+            (
+                "account_name",
+                self.account_name.as_deref().unwrap_or(&self.account_id),
+            ),
+            ("role", &self.role),
+            ("alias", self.alias.as_deref().unwrap_or(&self.account_id)),
+            ("region", &self.region),
+        ]
+    }
 }
 
 impl std::panic::UnwindSafe for ExecJob {}
@@ -46,53 +142,101 @@ impl Job for ExecJob {
     type Output = usize;
 
     fn get_job_id(&self) -> &str {
-        &self.account_id
+        &self.job_id
     }
 
     fn execute(self) -> Result<Self::Output, Self::Error> {
+        let placeholders = self.placeholders();
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| render_placeholders(argument, &placeholders))
+            .collect::<Vec<_>>();
+        let mut env = vec![
+            ("AWS_ACCOUNT_ID", self.account_id.as_str()),
+            ("AWS_REGION", self.region.as_str()),
+            ("AWS_AUTH_ROLE", self.role.as_str()),
+        ];
+        if let Some(account_name) = &self.account_name {
+            env.push(("AWS_ACCOUNT_NAME", account_name));
+        }
+
         if self.suppress_output {
-            exec::<File, File>(
-                &self.account_id,
-                &self.arguments,
-                self.credentials,
-                &self.region,
-                true,
-                None,
-                None,
-            )
-        } else if let Some(base_path) = self.output_base_path {
-            let stdout_path = base_path.join(format!("{}-stdout.log", self.account_id));
-            let stderr_path = base_path.join(format!("{}-stderr.log", self.account_id));
+            exec::<File, File>(&arguments, &env, &self.credentials, true, None, None)
+        } else if let Some(base_path) = &self.output_base_path {
+            // Values such as account names may contain separators or `..`, each is kept to a
+            // single path component so files stay within the output directory
+            let path_values = placeholders
+                .iter()
+                .map(|(key, value)| (*key, sanitize_path_component(value)))
+                .collect::<Vec<_>>();
+            let path_placeholders = path_values
+                .iter()
+                .map(|(key, value)| (*key, value.as_str()))
+                .collect::<Vec<_>>();
+            let base_path = PathBuf::from(render_placeholders(
+                &base_path.to_string_lossy(),
+                &path_placeholders,
+            ));
+            std::fs::create_dir_all(&base_path)?;
+            let output_name = render_placeholders(&self.output_name, &path_placeholders);
+            let stdout_path = base_path.join(format!("{output_name}-stdout.log"));
+            let stderr_path = base_path.join(format!("{output_name}-stderr.log"));
             let mut stdout_file = File::create(stdout_path)?;
             let mut stderr_file = File::create(stderr_path)?;
             exec::<File, File>(
-                &self.account_id,
-                &self.arguments,
-                self.credentials,
-                &self.region,
+                &arguments,
+                &env,
+                &self.credentials,
                 false,
                 Some(&mut stdout_file),
                 Some(&mut stderr_file),
             )
-        } else {
-            exec::<File, File>(
-                &self.account_id,
-                &self.arguments,
-                self.credentials,
-                &self.region,
+        } else if self.group_output {
+            let prefix = self.output_prefix.clone().unwrap_or_default();
+            let mut stdout_writer = PrefixWriter::new(prefix.clone(), Vec::new());
+            let mut stderr_writer = PrefixWriter::new(prefix, Vec::new());
+            let result = exec(
+                &arguments,
+                &env,
+                &self.credentials,
                 false,
-                None,
-                None,
-            )
+                Some(&mut stdout_writer),
+                Some(&mut stderr_writer),
+            );
+            stdout_writer.flush()?;
+            stderr_writer.flush()?;
+            // Both locks are held so the output of another account cannot slip in between
+            let mut stdout = io::stdout().lock();
+            let mut stderr = io::stderr().lock();
+            stdout.write_all(&stdout_writer.into_inner())?;
+            stdout.flush()?;
+            stderr.write_all(&stderr_writer.into_inner())?;
+            result
+        } else if let Some(prefix) = &self.output_prefix {
+            let mut stdout_writer = PrefixWriter::new(prefix.clone(), io::stdout());
+            let mut stderr_writer = PrefixWriter::new(prefix.clone(), io::stderr());
+            let result = exec(
+                &arguments,
+                &env,
+                &self.credentials,
+                false,
+                Some(&mut stdout_writer),
+                Some(&mut stderr_writer),
+            );
+            stdout_writer.flush()?;
+            stderr_writer.flush()?;
+            result
+        } else {
+            exec::<File, File>(&arguments, &env, &self.credentials, false, None, None)
         }
     }
 }
 
-fn exec<W1: Write + Send + 'static, W2: Write + Send + 'static>(
-    account_id: &str,
+fn exec<W1: Write + Send, W2: Write + Send>(
     arguments: &[String],
-    credentials: Credentials,
-    region: &str,
+    env: &[(&str, &str)],
+    credentials: &Credentials,
     suppress_output: bool,
     redirect_stdout: Option<&mut W1>,
     redirect_stderr: Option<&mut W2>,
@@ -103,9 +247,8 @@ fn exec<W1: Write + Send + 'static, W2: Write + Send + 'static>(
     let mut command = Command::new(program);
     command.args(args);
 
-    // Set credentials
-    command.env("AWS_ACCOUNT_ID", account_id);
-    command.env("AWS_REGION", region);
+    // Set account metadata and credentials
+    command.envs(env.iter().copied());
     command.env("AWS_ACCESS_KEY_ID", credentials.access_key_id());
     command.env("AWS_SECRET_ACCESS_KEY", credentials.secret_access_key());
     if let Some(token) = credentials.session_token() {
@@ -176,3 +319,55 @@ fn exec<W1: Write + Send + 'static, W2: Write + Send + 'static>(
         Ok(status.code().unwrap_or(0) as usize)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prefixes_pads_to_longest_label() {
+        assert_eq!(
+            render_prefixes(&["dev", "production"], false),
+            ["[dev]        ", "[production] "]
+        );
+        assert!(render_prefixes(&["dev"], true)[0].starts_with("\x1b[36m[dev]"));
+    }
+
+    #[test]
+    fn test_placeholders_fall_back_to_account_id() {
+        let job = ExecJob {
+            job_id: "123456789012/eu-west-1".to_string(),
+            account_id: "123456789012".to_string(),
+            credentials: Credentials::new("AKID", "SECRET", None, None, "test"),
+            region: Arc::new("eu-west-1".to_string()),
+            arguments: Arc::from(["aws".to_string()]),
+            account_name: None,
+            role: "ReadOnly".to_string(),
+            alias: None,
+            suppress_output: false,
+            output_base_path: None,
+            output_name: Arc::from("{account_id}"),
+            output_prefix: None,
+            group_output: false,
+        };
+        assert_eq!(
+            render_placeholders(
+                "s3://logs-{account_name}-{alias}/{role}/{region}",
+                &job.placeholders()
+            ),
+            "s3://logs-123456789012-123456789012/ReadOnly/eu-west-1"
+        );
+    }
+
+    #[test]
+    fn test_prefix_writer_prefixes_complete_lines() {
+        let mut writer = PrefixWriter::new("[dev] ".to_string(), Vec::new());
+        writer.write_all(b"first\nsec").unwrap();
+        writer.write_all(b"ond\nlast").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(
+            String::from_utf8(writer.into_inner()).unwrap(),
+            "[dev] first\n[dev] second\n[dev] last\n"
+        );
+    }
+}
@@ -1,26 +1,36 @@
 mod exec;
+mod report;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use crate::utils::worker::ThreadPool;
 use aws_sdk_ssooidc::config::Credentials;
 use exec::ExecJob;
 use regex::Regex;
+use report::{JobSummary, PlanEntry};
+use std::io::IsTerminal;
 use std::sync::Arc;
 
 use crate::{
     alias_providers::{self, AliasProviderError, ProvideAliases},
     aws_sso::{
-        build_sso_mgr_manual, cache::ManageCache, AwsSsoManagerError, CacheManager,
-        CacheManagerError,
+        build_sso_mgr_manual, cache::ManageCache, config::AwsSsoConfig, AwsSsoConfigError,
+        AwsSsoManagerError, CacheManager, CacheManagerError, SsoRequestLimits,
     },
-    cmd::Batch,
+    cmd::{Batch, ColorMode, RoleStrategy},
     elog,
-    utils::resolve_config_dir,
+    utils::{
+        credentials_file::{default_credentials_file, write_credentials_profiles},
+        prompt,
+        region::{self, resolve_region},
+        resolve_config_dir,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
     #[error("Cache error: {0}")]
     Cache(#[from] CacheManagerError),
     #[error("Error getting credentials from AWS SSO: {0}")]
@@ -33,6 +43,16 @@ pub enum Error {
     Regex(#[from] regex::Error),
     #[error("Command Input validation failed: {0}")]
     ValidationFailed(String),
+    #[error("Error resolving region: {0}")]
+    Region(String),
+    #[error("Error writing credentials file {0:?}: {1}")]
+    WriteProfiles(std::path::PathBuf, std::io::Error),
+    #[error("Error formatting report: {0}")]
+    Report(#[from] serde_json::Error),
+    #[error("Command failed in {0} of {1} jobs")]
+    JobsFailed(usize, usize),
+    #[error("Error reading confirmation: {0}")]
+    Confirm(std::io::Error),
 }
 
 impl From<AwsSsoManagerError> for Error {
@@ -41,22 +61,164 @@ impl From<AwsSsoManagerError> for Error {
     }
 }
 
+/// How the roles of accounts targeted without aliases are chosen
+enum RoleSelection<'a> {
+    /// Roles attempted in this order, without listing the roles of the account
+    Order(&'a [String]),
+    /// Roles of the account matching the regex, by name
+    Regex(Regex),
+    /// Roles of the account in the order of the preference list
+    Preference(&'a [String]),
+}
+
+impl RoleSelection<'_> {
+    /// Candidate roles among the roles the account offers, in priority order.
+    fn select(&self, available: &[String]) -> Vec<String> {
+        match self {
+            // Attempted whether listed or not
+            RoleSelection::Order(roles) => roles.to_vec(),
+            RoleSelection::Regex(regex) => {
+                let mut roles = available
+                    .iter()
+                    .filter(|role| regex.is_match(role))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                roles.sort();
+                roles
+            }
+            RoleSelection::Preference(preference) => preference
+                .iter()
+                .filter(|role| available.contains(role))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Account left out of the batch
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Unresolved {
+    account_id: String,
+    reason: String,
+    // An SSO or API error prevented the command from running, rather than no acceptable role
+    failed: bool,
+}
+
+impl Unresolved {
+    fn new(account_id: &str, err: &AwsSsoManagerError, reason: String) -> Self {
+        Unresolved {
+            account_id: account_id.to_string(),
+            reason,
+            failed: !err.is_access_denied(),
+        }
+    }
+}
+
+/// Maps each account to the role it was assumed with and its credentials. Accounts that
+/// could not be assumed with any of their roles are added to `unresolved` with the reason.
+fn collect_credentials(
+    candidates: &[(String, Vec<String>)],
+    resolved: Vec<Result<(String, Credentials), AwsSsoManagerError>>,
+    debug: bool,
+    unresolved: &mut Vec<Unresolved>,
+) -> HashMap<String, (String, Credentials)> {
+    let mut credentials_map = HashMap::new();
+    for ((account_id, roles), result) in candidates.iter().zip(resolved) {
+        match result {
+            Ok((role_name, credentials)) => {
+                elog!(debug, "Succesffuly resolved credentials for account {account_id} using the {role_name} role");
+                credentials_map.insert(account_id.clone(), (role_name, credentials));
+            }
+            Err(err) => {
+                elog!(
+                    debug,
+                    "Unable to resolve credentials for account {account_id} using roles {}: {err}",
+                    roles.join(", ")
+                );
+                let reason = if err.is_access_denied() {
+                    format!("None of the roles {} could be assumed", roles.join(", "))
+                } else {
+                    format!("Unable to assume roles {}: {err}", roles.join(", "))
+                };
+                unresolved.push(Unresolved::new(account_id, &err, reason));
+            }
+        }
+    }
+    credentials_map
+}
+
+/// Warns about every account left out of the batch, sorted by account ID.
+fn warn_unresolved(unresolved: &mut [Unresolved]) {
+    unresolved.sort();
+    for Unresolved {
+        account_id, reason, ..
+    } in unresolved.iter()
+    {
+        eprintln!(
+            "WARNING: Not running in account {account_id}: {}",
+            reason.trim_end()
+        );
+    }
+}
+
 pub async fn exec_batch(subcommand: Batch) -> Result<(), Error> {
     match &subcommand {
-        Batch::Exec { arguments, .. } => {
+        Batch::Exec {
+            arguments, confirm, ..
+        } => {
             exec::ExecJob::validate(arguments)
                 .map_err(|err| Error::ValidationFailed(err.to_string()))?;
+            if *confirm && !prompt::is_interactive() {
+                return Err(Error::ValidationFailed(
+                    "--confirm requires an interactive terminal".to_string(),
+                ));
+            }
         }
+        Batch::WriteProfiles { .. } => {}
     }
 
     let batch_common = subcommand.get_common_args();
     let config_dir = resolve_config_dir(batch_common.config_dir.as_deref());
+    let (settings, sources) = AwsSsoConfig::load_effective_config_with_sources(&config_dir)?;
+    let (pinned_region, default_region) = settings.configured_regions(&sources);
+    let region = resolve_region(
+        batch_common.region.as_deref(),
+        None,
+        pinned_region,
+        default_region,
+        &settings.sso_region,
+    )
+    .map_err(Error::Region)?;
     let cache_dir = batch_common.sso_cache_dir.as_deref().unwrap_or(&config_dir);
     let mut cache_manager = CacheManager::new(cache_dir);
-    let mut alias_provider = alias_providers::build_alias_provider(&config_dir);
-    let mut sso_manager = build_sso_mgr_manual(&mut cache_manager, &config_dir);
+    let mut alias_provider =
+        alias_providers::build_alias_provider(&config_dir, batch_common.team_aliases.as_deref())?;
+    let mut sso_manager = build_sso_mgr_manual(&mut cache_manager, &config_dir)?;
     sso_manager.load_cache(batch_common.ignore_cache);
 
+    // Account names label the exec report, accounts are listed anyway when not targeted explicitly
+    let accounts = if matches!(subcommand, Batch::Exec { .. })
+        || (batch_common.aliases.is_none() && batch_common.account_ids.is_none())
+    {
+        sso_manager.list_accounts(batch_common.ignore_cache).await?
+    } else {
+        Vec::new()
+    };
+    let account_names: HashMap<String, String> = accounts
+        .iter()
+        .filter_map(|ai| Some((ai.account_id()?.to_string(), ai.account_name()?.to_string())))
+        .collect();
+
+    let limits = SsoRequestLimits {
+        concurrency: batch_common.resolve_concurrency,
+        requests_per_second: batch_common.rate_limit,
+        max_retries: batch_common.max_retries,
+    };
+    // Accounts left out of the batch with the reason
+    let mut unresolved: Vec<Unresolved> = Vec::new();
+
+    // First alias targeting each account, used to name per account outputs
+    let mut account_aliases: HashMap<String, String> = HashMap::new();
     let grouped_possible_assumes: Vec<(String, String)> = if let Some(ref aliases) =
         batch_common.aliases
     {
@@ -65,6 +227,9 @@ pub async fn exec_batch(subcommand: Batch) -> Result<(), Error> {
             .iter()
             .filter_map(|alias| {
                 if let Ok(Some(assume_identity)) = alias_provider.get_alias(alias) {
+                    account_aliases
+                        .entry(assume_identity.account.to_string())
+                        .or_insert_with(|| alias.clone());
                     Some((
                         assume_identity.account.to_string(),
                         assume_identity.role.to_string(),
@@ -75,110 +240,372 @@ pub async fn exec_batch(subcommand: Batch) -> Result<(), Error> {
             })
             .collect::<Vec<_>>()
     } else {
-        let role_order = batch_common
-            .role_order
-            .as_deref()
-            .ok_or(Error::MissingRequiredArg(
-                "Missing required input role_oder".to_string(),
-            ))?;
-        if let Some(account_ids) = &batch_common.account_ids {
-            account_ids
-                .iter()
-                .flat_map(|account_id| {
-                    role_order
-                        .iter()
-                        .map(move |role| (account_id.to_string(), role.to_string()))
-                })
-                .collect::<Vec<_>>()
+        let selection = match (
+            &batch_common.role_order,
+            &batch_common.role_regex,
+            batch_common.role,
+        ) {
+            (Some(role_order), _, _) => RoleSelection::Order(role_order),
+            (None, Some(role_regex), _) => RoleSelection::Regex(Regex::new(role_regex)?),
+            (None, None, Some(RoleStrategy::Auto)) => RoleSelection::Preference(
+                settings.role_preference.as_deref().ok_or(Error::MissingRequiredArg(
+                    "--role auto requires role_preference, set it with `aws-auth config set role_preference <roles>`"
+                        .to_string(),
+                ))?,
+            ),
+            (None, None, None) => RoleSelection::Preference(
+                settings.role_preference.as_deref().ok_or(Error::MissingRequiredArg(
+                    "Provide --role-order, --role-regex or --role auto".to_string(),
+                ))?,
+            ),
+        };
+        let account_ids = if let Some(account_ids) = &batch_common.account_ids {
+            account_ids.clone()
         } else if let Some(account_name_regex) = &batch_common.account_filter_regex {
             let regex = Regex::new(&format!("^{}", account_name_regex))?;
 
-            sso_manager
-                .list_accounts(batch_common.ignore_cache)
-                .await?
-                .into_iter()
+            accounts
+                .iter()
                 .filter(|ai| {
                     ai.account_name.as_ref().is_some()
                         && regex.is_match(ai.account_name().unwrap())
                         && ai.account_id().is_some()
                 })
-                .flat_map(|ai| {
-                    let account_id = ai.account_id().unwrap().to_string();
-                    role_order
-                        .iter()
-                        .map(move |role| (account_id.clone(), role.to_string()))
-                })
+                .map(|ai| ai.account_id().unwrap().to_string())
                 .collect::<Vec<_>>()
         } else {
-            sso_manager
-                .list_accounts(batch_common.ignore_cache)
-                .await?
-                .into_iter()
-                .filter(|ai| ai.account_id().is_some())
-                .flat_map(|ai| {
-                    let account_id = ai.account_id().unwrap().to_string();
+            accounts
+                .iter()
+                .filter_map(|ai| ai.account_id().map(str::to_string))
+                .collect::<Vec<_>>()
+        };
+
+        if let RoleSelection::Order(role_order) = selection {
+            account_ids
+                .iter()
+                .flat_map(|account_id| {
                     role_order
                         .iter()
-                        .map(move |role| (account_id.clone(), role.to_string()))
+                        .map(move |role| (account_id.to_string(), role.to_string()))
                 })
                 .collect::<Vec<_>>()
+        } else {
+            let listed = sso_manager
+                .list_roles_of_accounts(&account_ids, limits, batch_common.ignore_cache)
+                .await?;
+            let mut possible_assumes = Vec::new();
+            for (account_id, roles) in account_ids.iter().zip(listed) {
+                let roles = match roles {
+                    Ok(roles) => roles,
+                    Err(err) => {
+                        let reason = format!("Unable to list roles: {err}");
+                        unresolved.push(Unresolved::new(account_id, &err, reason));
+                        continue;
+                    }
+                };
+                let selected = selection.select(&roles);
+                if selected.is_empty() {
+                    unresolved.push(Unresolved {
+                        account_id: account_id.clone(),
+                        reason: format!("No acceptable role among {}", roles.join(", ")),
+                        failed: false,
+                    });
+                }
+                possible_assumes
+                    .extend(selected.into_iter().map(|role| (account_id.clone(), role)));
+            }
+            possible_assumes
         }
     };
 
-    let mut credentials_map: HashMap<String, Credentials> = HashMap::new();
+    // Roles of each account in priority order
+    let mut candidates: Vec<(String, Vec<String>)> = Vec::new();
     for (account_id, role_name) in grouped_possible_assumes {
-        if credentials_map.contains_key(&account_id) {
-            continue;
-        }
-        match sso_manager
-            .assume_role(&account_id, &role_name, false, batch_common.ignore_cache)
-            .await
-        {
-            Ok(credentials) => {
-                elog!(batch_common.debug, "Succesffuly resolved credentials for account {account_id} using the {role_name} role");
-                credentials_map.insert(account_id.clone(), credentials);
-            }
-            Err(err) => {
-                if let AwsSsoManagerError::SsoGetRoleCredentials(_) = err {
-                    elog!(batch_common.debug, "Unauthorized to resolve credentials for account {account_id} using the {role_name} role");
-                } else {
-                    Err(Error::AwsSso(Box::new(err)))?;
-                }
-            }
+        match candidates.iter_mut().find(|(id, _)| *id == account_id) {
+            Some((_, roles)) if !roles.contains(&role_name) => roles.push(role_name),
+            Some(_) => {}
+            None => candidates.push((account_id, vec![role_name])),
         }
     }
-
-    cache_manager.commit()?;
+    let ignore_cache = batch_common.ignore_cache;
+    let debug = batch_common.debug;
 
     match subcommand {
         Batch::Exec {
             arguments,
             suppress_output,
             output_dir,
+            output_name,
+            regions,
+            all_regions,
+            prefix,
+            group,
+            color,
+            report,
+            allow_failures,
+            dry_run,
+            resolve_credentials,
+            confirm,
             batch_common,
         } => {
             let arguments: Arc<[String]> = Arc::from(arguments.into_boxed_slice());
             let _ = &arguments
                 .first()
                 .ok_or(Error::MissingRequiredArg("Missing program".to_string()))?;
+            let mut regions = match (regions, all_regions) {
+                (Some(regions), _) => regions,
+                (None, true) => settings.batch_regions.clone().unwrap_or_else(|| {
+                    region::find_partition(&region).map_or_else(
+                        || vec![region.clone()],
+                        |partition| partition.default_regions().map(str::to_string).collect(),
+                    )
+                }),
+                (None, false) => vec![region.clone()],
+            };
+            // Repeated regions would schedule the same job twice
+            let mut seen = HashSet::new();
+            regions.retain(|job_region| seen.insert(job_region.clone()));
+            // Jobs of one account in several regions would write the same log files
+            if let (Some(output_dir), Some(output_name)) = (&output_dir, &output_name) {
+                if regions.len() > 1
+                    && !output_name.contains("{region}")
+                    && !output_dir.to_string_lossy().contains("{region}")
+                {
+                    return Err(Error::ValidationFailed(
+                        "--output-name or --output-dir must contain {region} when running in several regions"
+                            .to_string(),
+                    ));
+                }
+            }
+            let output_dir = output_dir.map(Arc::new);
+            let output_name: Arc<str> = Arc::from(output_name.unwrap_or_else(|| {
+                if regions.len() > 1 {
+                    "{account_id}-{region}".to_string()
+                } else {
+                    "{account_id}".to_string()
+                }
+            }));
+            let regions = regions.into_iter().map(Arc::new).collect::<Vec<_>>();
+            let multi_region = regions.len() > 1;
+
+            // Prefixes are rendered for every candidate account up front so they line up
+            // whichever accounts resolve first
+            let labels = candidates
+                .iter()
+                .flat_map(|(account_id, _)| {
+                    let label = account_aliases
+                        .get(account_id)
+                        .or(account_names.get(account_id))
+                        .unwrap_or(account_id);
+                    regions.iter().map(move |job_region| {
+                        if multi_region {
+                            format!("{label}/{job_region}")
+                        } else {
+                            label.to_string()
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            let color = match color {
+                ColorMode::Always => true,
+                ColorMode::Never => false,
+                ColorMode::Auto => {
+                    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+                }
+            };
+            let prefixes = exec::render_prefixes(
+                &labels.iter().map(String::as_str).collect::<Vec<_>>(),
+                color,
+            );
+
+            if dry_run && !resolve_credentials {
+                warn_unresolved(&mut unresolved);
+                let plan = candidates
+                    .iter()
+                    .flat_map(|(account_id, roles)| {
+                        regions.iter().map(|job_region| PlanEntry {
+                            account_id: account_id.clone(),
+                            account_name: account_names.get(account_id).cloned(),
+                            roles: roles.clone(),
+                            region: job_region.to_string(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!("{}", report::format_plan(&plan, &report)?);
+                return Ok(());
+            }
+
+            // One job per account and region sharing the credentials of the account, started as
+            // soon as the credentials are resolved unless the whole batch is listed or confirmed first
             let worker_pool: ThreadPool<ExecJob> =
                 ThreadPool::new(batch_common.parallel, batch_common.debug);
-            let output_dir = output_dir.map(Arc::new);
-            let region = Arc::new(batch_common.region);
-            for (account_id, credentials) in credentials_map {
-                worker_pool.execute(ExecJob {
-                    account_id,
-                    arguments: arguments.clone(),
-                    output_base_path: output_dir.clone(),
-                    credentials,
-                    suppress_output,
-                    region: region.clone(),
-                });
+            let mut job_targets: HashMap<String, (String, String)> = HashMap::new();
+            let mut submit = |index: usize, role: &String, credentials: &Credentials| {
+                let account_id = &candidates[index].0;
+                for (region_index, job_region) in regions.iter().enumerate() {
+                    let job_id = format!("{account_id}/{job_region}");
+                    job_targets
+                        .insert(job_id.clone(), (account_id.clone(), job_region.to_string()));
+                    let output_prefix = &prefixes[index * regions.len() + region_index];
+                    worker_pool.execute(ExecJob {
+                        job_id,
+                        account_id: account_id.clone(),
+                        account_name: account_names.get(account_id).cloned(),
+                        role: role.clone(),
+                        alias: account_aliases.get(account_id).cloned(),
+                        output_name: output_name.clone(),
+                        arguments: arguments.clone(),
+                        output_base_path: output_dir.clone(),
+                        credentials: credentials.clone(),
+                        suppress_output,
+                        region: job_region.clone(),
+                        output_prefix: prefix.then(|| output_prefix.clone()),
+                        group_output: group,
+                    });
+                }
+            };
+            let deferred = dry_run || confirm;
+            let resolved = sso_manager
+                .assume_first_roles(&candidates, limits, false, ignore_cache, |index, result| {
+                    if let (false, Ok((role, credentials))) = (deferred, result) {
+                        submit(index, role, credentials);
+                    }
+                })
+                .await?;
+            let credentials_map =
+                collect_credentials(&candidates, resolved, debug, &mut unresolved);
+            cache_manager.commit()?;
+            warn_unresolved(&mut unresolved);
+
+            if dry_run {
+                let mut plan = credentials_map
+                    .iter()
+                    .flat_map(|(account_id, (role, _))| {
+                        regions.iter().map(|job_region| PlanEntry {
+                            account_id: account_id.clone(),
+                            account_name: account_names.get(account_id).cloned(),
+                            roles: vec![role.clone()],
+                            region: job_region.to_string(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                plan.sort_by(|a, b| (&a.account_id, &a.region).cmp(&(&b.account_id, &b.region)));
+                println!("{}", report::format_plan(&plan, &report)?);
+                return Ok(());
+            }
+            if confirm {
+                let question = format!(
+                    "Run `{}` in {} account(s) and {} region(s), {} job(s) in total?",
+                    arguments.join(" "),
+                    credentials_map.len(),
+                    regions.len(),
+                    credentials_map.len() * regions.len()
+                );
+                if !prompt::confirm(&question, false).map_err(Error::Confirm)? {
+                    println!("INFO: Aborted, the command was not run");
+                    return Ok(());
+                }
+                for (index, (account_id, _)) in candidates.iter().enumerate() {
+                    if let Some((role, credentials)) = credentials_map.get(account_id) {
+                        submit(index, role, credentials);
+                    }
+                }
+            }
+
+            let results = worker_pool.wait();
+            elog!(batch_common.debug, "{results:?}");
+
+            let mut summaries = results
+                .into_iter()
+                .map(|result| {
+                    let (account_id, job_region) = job_targets
+                        .get(&result.job_id)
+                        .cloned()
+                        .expect("Every result belongs to a scheduled job");
+                    let role = credentials_map[&account_id].0.clone();
+                    let account_name = account_names.get(&account_id).cloned();
+                    JobSummary::new(result, account_id, account_name, role, job_region)
+                })
+                // Accounts without credentials did not run any of their jobs, one per region
+                .chain(unresolved.iter().flat_map(|unresolved| {
+                    let account_name = account_names.get(&unresolved.account_id).cloned();
+                    regions.iter().map(move |job_region| {
+                        JobSummary::not_run(
+                            unresolved.account_id.clone(),
+                            account_name.clone(),
+                            job_region.to_string(),
+                            unresolved.reason.trim_end().to_string(),
+                            unresolved.failed,
+                        )
+                    })
+                }))
+                .collect::<Vec<_>>();
+            summaries.sort_by(|a, b| (&a.account_id, &a.region).cmp(&(&b.account_id, &b.region)));
+            println!("{}", report::format_report(&summaries, &report)?);
+
+            // Both counts are of jobs, accounts without an acceptable role are left out of both
+            let attempted = summaries
+                .iter()
+                .filter(|summary| !summary.skipped)
+                .collect::<Vec<_>>();
+            let failed = attempted.iter().filter(|summary| summary.failed()).count();
+            if failed > 0 && !allow_failures {
+                return Err(Error::JobsFailed(failed, attempted.len()));
+            }
+        }
+        Batch::WriteProfiles {
+            profile_prefix,
+            credentials_file,
+            ..
+        } => {
+            let resolved = sso_manager
+                .assume_first_roles(&candidates, limits, false, ignore_cache, |_, _| {})
+                .await?;
+            let credentials_map =
+                collect_credentials(&candidates, resolved, debug, &mut unresolved);
+            cache_manager.commit()?;
+            warn_unresolved(&mut unresolved);
+
+            let credentials_file = credentials_file.unwrap_or_else(default_credentials_file);
+            let mut profiles = credentials_map
+                .iter()
+                .map(|(account_id, (_, credentials))| {
+                    let name = account_aliases.get(account_id).unwrap_or(account_id);
+                    (format!("{profile_prefix}{name}"), credentials)
+                })
+                .collect::<Vec<_>>();
+            profiles.sort_by(|a, b| a.0.cmp(&b.0));
+            let profiles = profiles
+                .iter()
+                .map(|(profile, credentials)| (profile.as_str(), *credentials))
+                .collect::<Vec<_>>();
+            write_credentials_profiles(&credentials_file, &profiles)
+                .map_err(|err| Error::WriteProfiles(credentials_file.clone(), err))?;
+            for (profile, _) in profiles {
+                println!(
+                    "INFO: Wrote credentials for profile {profile} to {}",
+                    credentials_file.display()
+                );
             }
-            let result = worker_pool.wait();
-            elog!(batch_common.debug, "{result:?}");
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_selection_among_listed_roles() {
+        let available = ["ReadOnly", "Admin", "AuditReadOnly"].map(String::from);
+        let regex = RoleSelection::Regex(Regex::new("ReadOnly$").unwrap());
+        assert_eq!(regex.select(&available), ["AuditReadOnly", "ReadOnly"]);
+
+        let preference = ["PowerUser", "ReadOnly", "Admin"].map(String::from);
+        let preference = RoleSelection::Preference(&preference);
+        assert_eq!(preference.select(&available), ["ReadOnly", "Admin"]);
+        assert!(preference.select(&[]).is_empty());
+    }
+}
@@ -0,0 +1,249 @@
+use super::exec::{self, ExecJob};
+use crate::cmd::OutputFormat;
+use crate::utils::formatters::{json::JsonFormatter, text::TextFormatter, TabularFormatter};
+use crate::utils::worker::{JobError, JobResult};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Outcome of the command in one account and region
+pub struct JobSummary {
+    pub account_id: String,
+    pub account_name: Option<String>,
+    pub role: String,
+    pub region: String,
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub error: Option<String>,
+    // The command did not run as no acceptable role was found for the account
+    pub skipped: bool,
+}
+
+impl JobSummary {
+    pub fn new(
+        result: JobResult<ExecJob>,
+        account_id: String,
+        account_name: Option<String>,
+        role: String,
+        region: String,
+    ) -> JobSummary {
+        let (exit_code, error) = match result.result {
+            Ok(exit_code) => (Some(exit_code as i32), None),
+            Err(JobError::Error(exec::Error::ExecutionFailed(code))) => {
+                (Some(code), Some(format!("Exited with code {code}")))
+            }
+            Err(err) => (None, Some(err.to_string())),
+        };
+        JobSummary {
+            account_id,
+            account_name,
+            role,
+            region,
+            exit_code,
+            duration: result.duration,
+            error,
+            skipped: false,
+        }
+    }
+
+    /// Summary of a job the command did not run, with the reason. Accounts without an
+    /// acceptable role are skipped, SSO and API errors are failures.
+    pub fn not_run(
+        account_id: String,
+        account_name: Option<String>,
+        region: String,
+        reason: String,
+        failed: bool,
+    ) -> JobSummary {
+        JobSummary {
+            account_id,
+            account_name,
+            role: "-".to_string(),
+            region,
+            exit_code: None,
+            duration: Duration::ZERO,
+            error: Some(reason),
+            skipped: !failed,
+        }
+    }
+
+    /// Skipped jobs do not count as failures
+    pub fn failed(&self) -> bool {
+        !self.skipped && self.error.is_some()
+    }
+}
+
+/// Account, roles and region the command would run in, listed by --dry-run
+pub struct PlanEntry {
+    pub account_id: String,
+    pub account_name: Option<String>,
+    // Candidate roles in priority order, the assumed role once credentials are resolved
+    pub roles: Vec<String>,
+    pub region: String,
+}
+
+/// Formats the dry run plan as a table, or as a json list for machine use.
+pub fn format_plan(
+    entries: &[PlanEntry],
+    output: &OutputFormat,
+) -> Result<String, serde_json::Error> {
+    match output {
+        OutputFormat::Json => {
+            let rows = entries.iter().map(|entry| {
+                [
+                    Value::from(entry.account_id.as_str()),
+                    Value::from(entry.account_name.as_deref()),
+                    Value::from(entry.roles.clone()),
+                    Value::from(entry.region.as_str()),
+                ]
+            });
+            JsonFormatter::new(Vec::new(), false)
+                .format(&["accountId", "accountName", "roles", "region"], rows)
+        }
+        OutputFormat::Text => {
+            let rows = entries.iter().map(|entry| {
+                [
+                    entry.account_id.clone(),
+                    entry.account_name.clone().unwrap_or_default(),
+                    entry.roles.join(", "),
+                    entry.region.clone(),
+                ]
+            });
+            Ok(TextFormatter::new(Vec::new(), false, " | ")
+                .format(&["Account Id", "Account Name", "Roles", "Region"], rows)
+                .expect("TextFormatter should not fail"))
+        }
+    }
+}
+
+/// Formats the summaries as a table, or as a json list for machine use.
+pub fn format_report(
+    summaries: &[JobSummary],
+    output: &OutputFormat,
+) -> Result<String, serde_json::Error> {
+    match output {
+        OutputFormat::Json => {
+            let rows = summaries.iter().map(|summary| {
+                [
+                    Value::from(summary.account_id.as_str()),
+                    Value::from(summary.account_name.as_deref()),
+                    Value::from(summary.role.as_str()),
+                    Value::from(summary.region.as_str()),
+                    Value::from(summary.exit_code),
+                    Value::from(summary.duration.as_secs_f64()),
+                    Value::from(summary.error.as_deref()),
+                    Value::from(summary.skipped),
+                ]
+            });
+            JsonFormatter::new(Vec::new(), false).format(
+                &[
+                    "accountId",
+                    "accountName",
+                    "role",
+                    "region",
+                    "exitCode",
+                    "durationSeconds",
+                    "error",
+                    "skipped",
+                ],
+                rows,
+            )
+        }
+        OutputFormat::Text => {
+            let rows = summaries.iter().map(|summary| {
+                [
+                    summary.account_id.clone(),
+                    summary.account_name.clone().unwrap_or_default(),
+                    summary.role.clone(),
+                    summary.region.clone(),
+                    summary
+                        .exit_code
+                        .map_or("-".to_string(), |code| code.to_string()),
+                    format!("{:.1}s", summary.duration.as_secs_f64()),
+                    summary.error.clone().unwrap_or_default(),
+                ]
+            });
+            Ok(TextFormatter::new(Vec::new(), false, " | ")
+                .format(
+                    &[
+                        "Account Id",
+                        "Account Name",
+                        "Role",
+                        "Region",
+                        "Exit Code",
+                        "Duration",
+                        "Error",
+                    ],
+                    rows,
+                )
+                .expect("TextFormatter should not fail"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_summary_keeps_exit_code_of_failed_command() {
+        let summary = JobSummary::new(
+            JobResult {
+                job_id: "123456789012/eu-west-1".to_string(),
+                duration: Duration::from_millis(1500),
+                result: Err(JobError::Error(exec::Error::ExecutionFailed(3))),
+            },
+            "123456789012".to_string(),
+            Some("dev".to_string()),
+            "ReadOnly".to_string(),
+            "eu-west-1".to_string(),
+        );
+        assert!(summary.failed());
+        assert_eq!(summary.exit_code, Some(3));
+
+        let report = format_report(&[summary], &OutputFormat::Json).unwrap();
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report[0]["accountName"], "dev");
+        assert_eq!(report[0]["region"], "eu-west-1");
+        assert_eq!(report[0]["exitCode"], 3);
+        assert_eq!(report[0]["durationSeconds"], 1.5);
+        assert_eq!(report[0]["skipped"], false);
+    }
+
+    #[test]
+    fn test_plan_lists_roles_in_priority_order() {
+        let plan = [PlanEntry {
+            account_id: "123456789012".to_string(),
+            account_name: None,
+            roles: vec!["ReadOnly".to_string(), "Admin".to_string()],
+            region: "eu-west-1".to_string(),
+        }];
+        let report: Value =
+            serde_json::from_str(&format_plan(&plan, &OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(report[0]["roles"][1], "Admin");
+        assert!(format_plan(&plan, &OutputFormat::Text)
+            .unwrap()
+            .contains("ReadOnly, Admin"));
+    }
+
+    #[test]
+    fn test_skipped_account_is_not_a_failure() {
+        let summary = JobSummary::not_run(
+            "123456789012".to_string(),
+            None,
+            "eu-west-1".to_string(),
+            "No acceptable role among Admin".to_string(),
+            false,
+        );
+        assert!(!summary.failed());
+        let throttled = JobSummary::not_run(
+            "210987654321".to_string(),
+            None,
+            "eu-west-1".to_string(),
+            "Unable to list roles: TooManyRequestsException".to_string(),
+            true,
+        );
+        assert!(throttled.failed());
+        let report = format_report(&[summary], &OutputFormat::Text).unwrap();
+        assert!(report.contains("No acceptable role among Admin"));
+    }
+}
@@ -0,0 +1,403 @@
+use crate::aws_sso::config::{AwsSsoConfig, CONFIG_FILE_NAME, CONFIG_KEYS};
+use crate::aws_sso::{
+    AwsSsoConfigError, DEFAULT_CREATE_TOKEN_INITIAL_DELAY, DEFAULT_CREATE_TOKEN_LOCK_DECAY,
+    DEFAULT_CREATE_TOKEN_LOCK_THRESHOLD, DEFAULT_CREATE_TOKEN_MAX_ATTEMPTS,
+    DEFAULT_CREATE_TOKEN_RETRY_INTERVAL,
+};
+use crate::cmd::{Config, OutputFormat};
+use crate::commands::init::is_start_url;
+use crate::utils::formatters::{json::JsonFormatter, text::TextFormatter, TabularFormatter};
+use crate::utils::{prompt, region, resolve_config_dir};
+use regex::Regex;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error formatting config using json output: {0}")]
+    JsonFormatter(#[from] serde_json::Error),
+    #[error("Config validation failed with {0} error(s)")]
+    Invalid(usize),
+    #[error("Error editing config: {0}")]
+    Edit(io::Error),
+    #[error("Config edit aborted, no changes were saved")]
+    EditAborted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IssueLevel {
+    Error,
+    Warning,
+}
+
+struct ValidationIssue {
+    level: IssueLevel,
+    key: &'static str,
+    message: String,
+}
+
+fn default_value(key: &str) -> Option<String> {
+    let format_duration = |d: chrono::Duration| {
+        humantime::format_duration(d.to_std().expect("Defaults are positive")).to_string()
+    };
+    match key {
+        "max_attempts" => Some(DEFAULT_CREATE_TOKEN_MAX_ATTEMPTS.to_string()),
+        "initial_delay" => Some(format_duration(DEFAULT_CREATE_TOKEN_INITIAL_DELAY)),
+        "retry_interval" => Some(format_duration(DEFAULT_CREATE_TOKEN_RETRY_INTERVAL)),
+        "create_token_retry_threshold" => Some(DEFAULT_CREATE_TOKEN_LOCK_THRESHOLD.to_string()),
+        "create_token_lock_decay" => Some(format_duration(DEFAULT_CREATE_TOKEN_LOCK_DECAY)),
+        _ => None,
+    }
+}
+
+fn validate_config(config: &AwsSsoConfig, config_dir: &Path) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let url_regex = Regex::new(r"^https://([A-Za-z0-9-]+(\.[A-Za-z0-9-]+)+)(/\S*)?$")
+        .expect("Static regex should be valid");
+
+    match url_regex.captures(&config.start_url) {
+        None => issues.push(ValidationIssue {
+            level: IssueLevel::Error,
+            key: "start_url",
+            message: format!(
+                "{:?} is not an https URL, expected a URL such as https://my-company.awsapps.com/start",
+                config.start_url
+            ),
+        }),
+        Some(captures) => {
+            let host = captures[1].to_lowercase();
+            let path = captures.get(3).map_or("", |m| m.as_str());
+            if !is_start_url(&host, path.trim_start_matches('/')) {
+                issues.push(ValidationIssue {
+                    level: IssueLevel::Warning,
+                    key: "start_url",
+                    message: format!(
+                        "{:?} does not look like an IAM Identity Center start URL (https://<name>.awsapps.com/start or https://ssoins-<id>.portal.<region>.app.aws)",
+                        config.start_url
+                    ),
+                });
+            }
+        }
+    }
+
+    if let Err(message) = region::validate_region(&config.sso_region) {
+        issues.push(ValidationIssue {
+            level: IssueLevel::Error,
+            key: "sso_region",
+            message,
+        });
+    }
+
+    if let Some(Err(message)) = config.region.as_deref().map(region::validate_region) {
+        issues.push(ValidationIssue {
+            level: IssueLevel::Error,
+            key: "region",
+            message,
+        });
+    }
+
+    if config.max_attempts == Some(0) {
+        issues.push(ValidationIssue {
+            level: IssueLevel::Warning,
+            key: "max_attempts",
+            message: "Token creation will not be retried while waiting for browser approval"
+                .to_string(),
+        });
+    }
+
+    let probe_path = config_dir.join(".aws-auth-write-probe");
+    if let Err(err) =
+        std::fs::write(&probe_path, b"").and_then(|_| std::fs::remove_file(&probe_path))
+    {
+        issues.push(ValidationIssue {
+            level: IssueLevel::Error,
+            key: "config_dir",
+            message: format!("{} is not writable: {err}", config_dir.display()),
+        });
+    }
+
+    issues
+}
+
+fn print_issues(issues: &[ValidationIssue]) -> usize {
+    for issue in issues {
+        let level = match issue.level {
+            IssueLevel::Error => "ERROR",
+            IssueLevel::Warning => "WARNING",
+        };
+        eprintln!("{level}: {}: {}", issue.key, issue.message);
+    }
+    issues
+        .iter()
+        .filter(|issue| issue.level == IssueLevel::Error)
+        .count()
+}
+
+fn resolve_editor() -> String {
+    std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        })
+}
+
+fn edit_config(config_dir: &Path) -> Result<(), Error> {
+    // Loading first migrates a legacy config.json so there is always a config.toml to edit
+    let config = AwsSsoConfig::load_config(config_dir)?;
+    let config_path = config_dir.join(CONFIG_FILE_NAME);
+    let edit_path = config_dir.join("config.edit.toml");
+    let original = std::fs::read_to_string(&config_path).unwrap_or(config.to_toml_string()?);
+    std::fs::write(&edit_path, &original).map_err(Error::Edit)?;
+
+    let editor = resolve_editor();
+    let mut editor_parts = editor.split_whitespace();
+    let program = editor_parts.next().unwrap_or("vi");
+    let editor_args = editor_parts.collect::<Vec<_>>();
+
+    loop {
+        let status = Command::new(program)
+            .args(&editor_args)
+            .arg(&edit_path)
+            .status()
+            .map_err(Error::Edit)?;
+        if !status.success() {
+            let _ = std::fs::remove_file(&edit_path);
+            return Err(Error::EditAborted);
+        }
+
+        let content = std::fs::read_to_string(&edit_path).map_err(Error::Edit)?;
+        if content == original {
+            let _ = std::fs::remove_file(&edit_path);
+            println!("INFO: No changes made to {}", config_path.display());
+            return Ok(());
+        }
+
+        let errors = match AwsSsoConfig::from_toml_str(&content, &config_path) {
+            Ok(edited) => print_issues(&validate_config(&edited, config_dir)),
+            Err(err) => {
+                eprintln!("ERROR: {err}");
+                1
+            }
+        };
+        if errors == 0 {
+            std::fs::rename(&edit_path, &config_path).map_err(Error::Edit)?;
+            println!("INFO: Successfully updated {}", config_path.display());
+            return Ok(());
+        }
+        if !prompt::confirm("Re-open the editor to fix the config?", true).map_err(Error::Edit)? {
+            let _ = std::fs::remove_file(&edit_path);
+            return Err(Error::EditAborted);
+        }
+    }
+}
+
+pub fn exec_config(subcommand: Config) -> Result<(), Error> {
+    match subcommand {
+        Config::Show { common, formatting } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            let (config, sources) = AwsSsoConfig::load_effective_config_with_sources(&config_dir)?;
+            let mut rows = Vec::with_capacity(CONFIG_KEYS.len());
+            for key in CONFIG_KEYS {
+                let (value, source) = match (config.get_value(key)?, default_value(key)) {
+                    (Some(value), _) => (
+                        value,
+                        sources
+                            .get(key)
+                            .map_or_else(String::new, ToString::to_string),
+                    ),
+                    (None, Some(default)) => (default, "default".to_string()),
+                    (None, None) => (String::new(), "unset".to_string()),
+                };
+                rows.push([key.to_string(), value, source]);
+            }
+            let omit_fields = formatting.omit_fields.iter().map(|v| v.as_str()).collect();
+
+            match formatting.output {
+                OutputFormat::Json => {
+                    let formatter = JsonFormatter::new(omit_fields, formatting.no_headers);
+                    let output = formatter.format(&["key", "value", "source"], rows)?;
+                    println!("{}", output)
+                }
+                OutputFormat::Text => {
+                    let formatter = TextFormatter::new(omit_fields, formatting.no_headers, " | ");
+                    let output = formatter
+                        .format(&["Key", "Value", "Source"], rows)
+                        .expect("TextFormatter should not fail");
+                    println!("{}", output)
+                }
+            }
+        }
+        Config::Get { common, key } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            let config = AwsSsoConfig::load_effective_config(&config_dir)?;
+            if let Some(value) = config.get_value(&key)?.or_else(|| default_value(&key)) {
+                println!("{value}");
+            }
+        }
+        Config::Set { common, key, value } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            let mut config = AwsSsoConfig::load_config(&config_dir)?;
+            config.set_value(&key, Some(&value))?;
+            let errors = print_issues(&validate_config(&config, &config_dir));
+            if errors > 0 {
+                return Err(Error::Invalid(errors));
+            }
+            config.save_config(&config_dir)?;
+        }
+        Config::Unset { common, key } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            let mut config = AwsSsoConfig::load_config(&config_dir)?;
+            config.set_value(&key, None)?;
+            config.save_config(&config_dir)?;
+        }
+        Config::Validate { common } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            let config = AwsSsoConfig::load_config(&config_dir)?;
+            let errors = print_issues(&validate_config(&config, &config_dir));
+            if errors > 0 {
+                return Err(Error::Invalid(errors));
+            }
+            println!(
+                "INFO: Config at {} is valid",
+                config_dir.join(CONFIG_FILE_NAME).display()
+            );
+        }
+        Config::Edit { common } => {
+            let config_dir = resolve_config_dir(common.config_dir.as_deref());
+            edit_config(&config_dir)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::ConfigCommonArgs;
+    use crate::commands::init::normalize_start_url;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn config_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("aws-auth-config-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        AwsSsoConfig::new(
+            "https://my-company.awsapps.com/start".to_string(),
+            "eu-west-1".to_string(),
+        )
+        .save_config(&dir)
+        .unwrap();
+        dir
+    }
+
+    fn common(dir: &Path) -> ConfigCommonArgs {
+        ConfigCommonArgs {
+            config_dir: Some(dir.to_path_buf()),
+        }
+    }
+
+    fn set(dir: &Path, key: &str, value: &str) -> Result<(), Error> {
+        exec_config(Config::Set {
+            common: common(dir),
+            key: key.to_string(),
+            value: value.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_set_rejects_invalid_values_without_saving() {
+        let dir = config_dir("set");
+        let saved = fs::read_to_string(dir.join(CONFIG_FILE_NAME)).unwrap();
+
+        assert!(matches!(
+            set(&dir, "max_attempts", "many"),
+            Err(Error::Config(AwsSsoConfigError::InvalidValue { .. }))
+        ));
+        assert!(matches!(
+            set(&dir, "retry_interval", "5 parsecs"),
+            Err(Error::Config(AwsSsoConfigError::InvalidValue { .. }))
+        ));
+        assert!(matches!(
+            set(&dir, "batch_regions", "eu-west-1,eu-wset-1"),
+            Err(Error::Config(AwsSsoConfigError::InvalidValue { .. }))
+        ));
+        assert!(matches!(
+            set(&dir, "version", "2"),
+            Err(Error::Config(AwsSsoConfigError::ReadOnlyKey(_)))
+        ));
+        assert!(matches!(
+            set(&dir, "colour", "blue"),
+            Err(Error::Config(AwsSsoConfigError::UnknownKey(_)))
+        ));
+        // Parsed but rejected by validation
+        assert!(matches!(
+            set(&dir, "region", "eu-wset-1"),
+            Err(Error::Invalid(1))
+        ));
+        assert_eq!(
+            fs::read_to_string(dir.join(CONFIG_FILE_NAME)).unwrap(),
+            saved
+        );
+
+        set(&dir, "max_attempts", "3").unwrap();
+        let config = AwsSsoConfig::load_config(&dir).unwrap();
+        assert_eq!(config.max_attempts, Some(3));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unset_and_validate() {
+        let dir = config_dir("unset");
+        set(&dir, "region", "us-east-1").unwrap();
+        exec_config(Config::Unset {
+            common: common(&dir),
+            key: "region".to_string(),
+        })
+        .unwrap();
+        let config = AwsSsoConfig::load_config(&dir).unwrap();
+        assert_eq!(config.get_value("region").unwrap(), None);
+
+        assert!(matches!(
+            exec_config(Config::Unset {
+                common: common(&dir),
+                key: "start_url".to_string(),
+            }),
+            Err(Error::Config(AwsSsoConfigError::InvalidValue { .. }))
+        ));
+
+        exec_config(Config::Validate {
+            common: common(&dir),
+        })
+        .unwrap();
+        let mut config = AwsSsoConfig::load_config(&dir).unwrap();
+        config.start_url = "http://my-company.awsapps.com/start".to_string();
+        config.sso_region = "mars-east-1".to_string();
+        config.save_config(&dir).unwrap();
+        assert!(matches!(
+            exec_config(Config::Validate {
+                common: common(&dir),
+            }),
+            Err(Error::Invalid(2))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate_accepts_regional_start_url() {
+        let dir = config_dir("regional");
+        let start_url = normalize_start_url("ssoins-1234.portal.eu-west-1.app.aws").unwrap();
+        let config = AwsSsoConfig::new(start_url, "eu-west-1".to_string());
+        assert!(validate_config(&config, &dir)
+            .iter()
+            .all(|issue| issue.key != "start_url"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,183 @@
+use crate::aws_sso::AwsSsoManagerError;
+use crate::utils::region;
+use aws_config::Region;
+use aws_sdk_ssooidc::config::Credentials;
+use reqwest::Url;
+use serde::Deserialize;
+
+const DEFAULT_ISSUER: &str = "aws-auth";
+
+pub struct ExecConsoleInputs<'a> {
+    pub region: Region,
+    pub destination: Option<&'a str>,
+    pub service: Option<&'a str>,
+    pub session_duration: Option<u64>,
+    pub federation_endpoint: Option<&'a str>,
+    pub print: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error resolving SSO credentials: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error(
+        "Credentials have no session token, the federation endpoint requires temporary credentials"
+    )]
+    MissingSessionToken,
+    #[error("Invalid federation endpoint {0:?}: {1}")]
+    InvalidEndpoint(String, String),
+    #[error("Error requesting sign-in token: {0}")]
+    SigninToken(#[from] reqwest::Error),
+    #[error("Error opening the console in the browser, use --print to print the URL instead: {0}")]
+    Browser(std::io::Error),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Deserialize)]
+struct SigninTokenResponse {
+    #[serde(rename = "SigninToken")]
+    signin_token: String,
+}
+
+/// Returns the federation endpoint and console domain of the partition the region belongs to.
+fn partition_endpoints(region: &str) -> (&'static str, &'static str) {
+    match region::find_partition(region).map(|p| p.id) {
+        Some("aws-cn") => (
+            "https://signin.amazonaws.cn/federation",
+            "console.amazonaws.cn",
+        ),
+        Some("aws-us-gov") => (
+            "https://signin.amazonaws-us-gov.com/federation",
+            "console.amazonaws-us-gov.com",
+        ),
+        _ => (
+            "https://signin.aws.amazon.com/federation",
+            "console.aws.amazon.com",
+        ),
+    }
+}
+
+/// Builds the console deep link, a full destination URL is used as is.
+fn destination_url(region: &str, destination: Option<&str>, service: Option<&str>) -> String {
+    let (_, console_domain) = partition_endpoints(region);
+    match (destination, service) {
+        (Some(destination), _) => destination.to_string(),
+        (None, Some(service)) => {
+            format!("https://{region}.{console_domain}/{service}/home?region={region}")
+        }
+        (None, None) => {
+            format!("https://{region}.{console_domain}/console/home?region={region}")
+        }
+    }
+}
+
+async fn get_signin_token(
+    endpoint: &str,
+    credentials: &Credentials,
+    session_duration: Option<u64>,
+) -> Result<String> {
+    let session = serde_json::json!({
+        "sessionId": credentials.access_key_id(),
+        "sessionKey": credentials.secret_access_key(),
+        "sessionToken": credentials.session_token().ok_or(Error::MissingSessionToken)?,
+    })
+    .to_string();
+    let mut params = vec![("Action", "getSigninToken".to_string())];
+    if let Some(session_duration) = session_duration {
+        params.push(("SessionDuration", session_duration.to_string()));
+    }
+    params.push(("Session", session));
+    let url = Url::parse_with_params(endpoint, &params)
+        .map_err(|err| Error::InvalidEndpoint(endpoint.to_string(), err.to_string()))?;
+
+    let response = reqwest::get(url)
+        .await?
+        .error_for_status()?
+        .json::<SigninTokenResponse>()
+        .await?;
+    Ok(response.signin_token)
+}
+
+fn login_url(endpoint: &str, destination: &str, signin_token: &str) -> Result<Url> {
+    Url::parse_with_params(
+        endpoint,
+        [
+            ("Action", "login"),
+            ("Issuer", DEFAULT_ISSUER),
+            ("Destination", destination),
+            ("SigninToken", signin_token),
+        ],
+    )
+    .map_err(|err| Error::InvalidEndpoint(endpoint.to_string(), err.to_string()))
+}
+
+pub async fn exec_console(credentials: Credentials, exec_inputs: ExecConsoleInputs<'_>) -> Result {
+    let region = exec_inputs.region.as_ref();
+    let endpoint = exec_inputs
+        .federation_endpoint
+        .unwrap_or(partition_endpoints(region).0);
+    let destination = destination_url(region, exec_inputs.destination, exec_inputs.service);
+
+    let signin_token =
+        get_signin_token(endpoint, &credentials, exec_inputs.session_duration).await?;
+    let url = login_url(endpoint, &destination, &signin_token)?;
+
+    if exec_inputs.print {
+        println!("{url}");
+    } else {
+        webbrowser::open(url.as_str()).map_err(Error::Browser)?;
+        println!("INFO: Opened the AWS console in the browser");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_signin_token_from_stub_endpoint() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/federation", listener.local_addr().unwrap());
+        let stub = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![0; 4096];
+            let read = stream.read(&mut request).await.unwrap();
+            let body = r#"{"SigninToken":"stub-token"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request[..read]).to_string()
+        });
+
+        let credentials =
+            Credentials::new("AKID", "SECRET", Some("TOKEN".to_string()), None, "test");
+        let token = get_signin_token(&endpoint, &credentials, Some(3600))
+            .await
+            .unwrap();
+        assert_eq!(token, "stub-token");
+        let request = stub.await.unwrap();
+        assert!(request
+            .starts_with("GET /federation?Action=getSigninToken&SessionDuration=3600&Session="));
+
+        let url = login_url(
+            &endpoint,
+            &destination_url("eu-west-1", None, Some("s3")),
+            &token,
+        )
+        .unwrap();
+        assert!(url.as_str().contains(
+            "Destination=https%3A%2F%2Feu-west-1.console.aws.amazon.com%2Fs3%2Fhome%3Fregion%3Deu-west-1"
+        ));
+    }
+}
@@ -0,0 +1,393 @@
+use crate::alias_providers::{self, AliasProviderError, ProvideAliases};
+use crate::aws_sso::{build_sso_mgr_cached, AwsSsoConfigError, AwsSsoManagerError};
+use crate::cmd::CredentialHelperAction;
+use crate::utils::{
+    atomic_write, region,
+    signed_request::{self, send_signed_request, SignedRequest},
+};
+use aws_config::Region;
+use aws_sdk_ssooidc::config::Credentials;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+const GET_AUTHORIZATION_TOKEN_TARGET: &str =
+    "AmazonEC2ContainerRegistry_V20150921.GetAuthorizationToken";
+// Output docker-credential-helpers clients expect when a registry is unknown
+const CREDENTIALS_NOT_FOUND: &str = "credentials not found in native keychain";
+
+pub struct ExecEcrLoginInputs<'a> {
+    pub region: Region,
+    pub registry_accounts: &'a [String],
+    pub docker_config: &'a Path,
+    pub ecr_endpoint: Option<&'a str>,
+    pub print: bool,
+}
+
+pub struct ExecCredentialHelperInputs<'a> {
+    pub action: CredentialHelperAction,
+    pub config_dir: &'a Path,
+    pub sso_cache_dir: Option<&'a Path>,
+    pub team_aliases: Option<&'a Path>,
+    pub ecr_endpoint: Option<&'a str>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error resolving SSO credentials: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error getting alias: {0}")]
+    AliasProvider(#[from] AliasProviderError),
+    #[error("Error requesting ECR authorization token: {0}")]
+    AuthorizationToken(#[from] signed_request::Error),
+    #[error("Invalid GetAuthorizationToken response: {0}")]
+    InvalidAuthorizationToken(String),
+    #[error("Error accessing docker config {0:?}: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("Invalid docker config {0:?}: {1}")]
+    InvalidDockerConfig(PathBuf, serde_json::Error),
+    #[error("Error reading credential helper input: {0}")]
+    Stdin(io::Error),
+    #[error("{CREDENTIALS_NOT_FOUND}: no alias for registry {0}")]
+    CredentialsNotFound(String),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GetAuthorizationTokenResponse {
+    authorization_data: Vec<AuthorizationData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthorizationData {
+    // base64 of AWS:<password>
+    authorization_token: String,
+    // Epoch seconds
+    expires_at: Option<f64>,
+    proxy_endpoint: String,
+}
+
+impl AuthorizationData {
+    fn registry(&self) -> &str {
+        registry_host(&self.proxy_endpoint)
+    }
+
+    fn password(&self) -> Result<String> {
+        let decoded = STANDARD
+            .decode(&self.authorization_token)
+            .map_err(|err| Error::InvalidAuthorizationToken(err.to_string()))?;
+        let decoded = String::from_utf8(decoded)
+            .map_err(|err| Error::InvalidAuthorizationToken(err.to_string()))?;
+        decoded
+            .split_once(':')
+            .map(|(_, password)| password.to_string())
+            .ok_or_else(|| {
+                Error::InvalidAuthorizationToken("token is not user:password".to_string())
+            })
+    }
+
+    fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+    }
+}
+
+#[derive(Serialize)]
+struct HelperCredentials<'a> {
+    #[serde(rename = "ServerURL")]
+    server_url: &'a str,
+    #[serde(rename = "Username")]
+    username: &'a str,
+    #[serde(rename = "Secret")]
+    secret: &'a str,
+}
+
+/// Strips the scheme and path of a registry URL, leaving the host docker keys auths on.
+fn registry_host(server_url: &str) -> &str {
+    let host = server_url.trim();
+    let host = host
+        .strip_prefix("https://")
+        .or_else(|| host.strip_prefix("http://"))
+        .unwrap_or(host);
+    host.split('/').next().unwrap_or_default()
+}
+
+/// Returns the account and region of an ECR registry host,
+/// `<account>.dkr.ecr[-fips].<region>.<partition domain>`.
+fn parse_registry_host(server_url: &str) -> Option<(&str, &str)> {
+    let mut parts = registry_host(server_url).split('.');
+    let account = parts.next()?;
+    let valid_account = account.len() == 12 && account.bytes().all(|b| b.is_ascii_digit());
+    let dkr = parts.next()?;
+    let ecr = parts.next()?;
+    let region = parts.next()?;
+    (valid_account && dkr == "dkr" && matches!(ecr, "ecr" | "ecr-fips"))
+        .then_some((account, region))
+}
+
+fn ecr_endpoint(region: &str, endpoint: Option<&str>) -> String {
+    endpoint.map(str::to_string).unwrap_or_else(|| {
+        let dns_suffix = region::find_partition(region).map_or("amazonaws.com", |p| p.dns_suffix);
+        format!("https://api.ecr.{region}.{dns_suffix}")
+    })
+}
+
+async fn get_authorization_token(
+    credentials: &Credentials,
+    endpoint: &str,
+    region: &str,
+    registry_ids: &[String],
+) -> Result<Vec<AuthorizationData>> {
+    let body = if registry_ids.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::json!({ "registryIds": registry_ids })
+    }
+    .to_string();
+    let response = send_signed_request(
+        credentials,
+        SignedRequest {
+            method: "POST",
+            url: &format!("{}/", endpoint.trim_end_matches('/')),
+            region,
+            service: "ecr",
+            headers: &[
+                ("x-amz-target", GET_AUTHORIZATION_TOKEN_TARGET),
+                ("content-type", "application/x-amz-json-1.1"),
+            ],
+            body: body.as_bytes(),
+        },
+    )
+    .await?;
+    let authorization_data = serde_json::from_str::<GetAuthorizationTokenResponse>(&response)
+        .map_err(|err| Error::InvalidAuthorizationToken(err.to_string()))?
+        .authorization_data;
+    if authorization_data.is_empty() {
+        return Err(Error::InvalidAuthorizationToken(
+            "no authorization data returned".to_string(),
+        ));
+    }
+    Ok(authorization_data)
+}
+
+/// Sets `auths.<registry>.auth` for every registry, keeping the rest of the docker config.
+/// Fails instead of overwriting a config whose document, auths or registry entries are not objects.
+fn merge_docker_config(content: &str, auths: &[(&str, &str)]) -> serde_json::Result<String> {
+    let not_an_object = |what: &str| serde::de::Error::custom(format!("{what} is not an object"));
+    let mut document = match content.trim() {
+        "" => Map::new(),
+        content => match serde_json::from_str::<Value>(content)? {
+            Value::Object(object) => object,
+            _ => return Err(not_an_object("the document")),
+        },
+    };
+    let entries = document
+        .entry("auths")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| not_an_object("auths"))?;
+    for (registry, auth) in auths {
+        entries
+            .entry(registry.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| not_an_object(&format!("auths.{registry}")))?
+            .insert("auth".to_string(), Value::String(auth.to_string()));
+    }
+    serde_json::to_string_pretty(&Value::Object(document))
+}
+
+/// Requests an ECR authorization token and writes it to the docker config, or prints the
+/// password when requested.
+pub async fn exec_login<F>(
+    mut credential_resolver: F,
+    exec_inputs: ExecEcrLoginInputs<'_>,
+) -> Result
+where
+    F: AsyncFnMut() -> std::result::Result<Credentials, AwsSsoManagerError>,
+{
+    let credentials = credential_resolver().await?;
+    let region = exec_inputs.region.as_ref();
+    let authorization_data = get_authorization_token(
+        &credentials,
+        &ecr_endpoint(region, exec_inputs.ecr_endpoint),
+        region,
+        exec_inputs.registry_accounts,
+    )
+    .await?;
+
+    if exec_inputs.print {
+        // The token is the same for every registry the role can pull from
+        println!("{}", authorization_data[0].password()?);
+        return Ok(());
+    }
+
+    let path = exec_inputs.docker_config.join("config.json");
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(Error::Io(path, err)),
+    };
+    let auths = authorization_data
+        .iter()
+        .map(|data| (data.registry(), data.authorization_token.as_str()))
+        .collect::<Vec<_>>();
+    let merged = merge_docker_config(&content, &auths)
+        .map_err(|err| Error::InvalidDockerConfig(path.clone(), err))?;
+    atomic_write(&path, &merged).map_err(|err| Error::Io(path.clone(), err))?;
+
+    let existing: Value = serde_json::from_str(&merged).unwrap_or_default();
+    if let Some(creds_store) = existing["credsStore"].as_str() {
+        eprintln!(
+            "WARNING: {} sets credsStore {creds_store}, docker reads credentials from it instead of auths",
+            path.display()
+        );
+    }
+    for data in &authorization_data {
+        println!(
+            "INFO: Logged in to {} in {}{}",
+            data.registry(),
+            path.display(),
+            data.expires_at()
+                .map_or(String::new(), |expires_at| format!(
+                    ", expires at {}",
+                    expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                ))
+        );
+    }
+    Ok(())
+}
+
+/// Answers a docker credential helper request. `get` maps the registry read from stdin to the
+/// first alias of its account, preferring aliases configured for the registry region.
+pub async fn exec_credential_helper(exec_inputs: ExecCredentialHelperInputs<'_>) -> Result {
+    match exec_inputs.action {
+        CredentialHelperAction::Store | CredentialHelperAction::Erase => return Ok(()),
+        CredentialHelperAction::List => {
+            println!("{{}}");
+            return Ok(());
+        }
+        CredentialHelperAction::Get => {}
+    }
+
+    let mut server_url = String::new();
+    io::stdin()
+        .read_to_string(&mut server_url)
+        .map_err(Error::Stdin)?;
+    let server_url = server_url.trim();
+    let Some((account, registry_region)) = parse_registry_host(server_url) else {
+        println!("{CREDENTIALS_NOT_FOUND}");
+        return Err(Error::CredentialsNotFound(server_url.to_string()));
+    };
+
+    let mut alias_provider =
+        alias_providers::build_alias_provider(exec_inputs.config_dir, exec_inputs.team_aliases)?;
+    alias_provider.load_aliases()?;
+    let mut role = None;
+    for [alias, alias_account, alias_role] in alias_provider.list_aliases()? {
+        if alias_account != account {
+            continue;
+        }
+        let in_region = alias_provider
+            .get_alias(alias)?
+            .and_then(|identity| identity.region)
+            == Some(registry_region);
+        if in_region || role.is_none() {
+            role = Some(alias_role.to_string());
+        }
+        if in_region {
+            break;
+        }
+    }
+    let Some(role) = role else {
+        println!("{CREDENTIALS_NOT_FOUND}");
+        return Err(Error::CredentialsNotFound(server_url.to_string()));
+    };
+
+    let mut sso_manager = build_sso_mgr_cached(exec_inputs.config_dir, exec_inputs.sso_cache_dir)?;
+    let credentials = sso_manager
+        .assume_role(account, &role, false, false)
+        .await?;
+    let authorization_data = get_authorization_token(
+        &credentials,
+        &ecr_endpoint(registry_region, exec_inputs.ecr_endpoint),
+        registry_region,
+        &[account.to_string()],
+    )
+    .await?;
+    let password = authorization_data[0].password()?;
+    let output = serde_json::to_string(&HelperCredentials {
+        server_url,
+        username: "AWS",
+        secret: &password,
+    })
+    .expect("Helper credentials always serialize");
+    println!("{output}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_registry_host() {
+        assert_eq!(
+            parse_registry_host("https://123456789012.dkr.ecr.eu-west-1.amazonaws.com/v2/"),
+            Some(("123456789012", "eu-west-1"))
+        );
+        assert_eq!(
+            parse_registry_host("123456789012.dkr.ecr-fips.us-gov-west-1.amazonaws.com"),
+            Some(("123456789012", "us-gov-west-1"))
+        );
+        assert_eq!(parse_registry_host("ghcr.io"), None);
+        assert_eq!(
+            parse_registry_host("12345.dkr.ecr.eu-west-1.amazonaws.com"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_docker_config_keeps_other_settings() {
+        let content = r#"{"auths":{"ghcr.io":{"auth":"Z2g="},"123456789012.dkr.ecr.eu-west-1.amazonaws.com":{"auth":"b2xk"}},"credHelpers":{"gcr.io":"gcloud"}}"#;
+        let merged = merge_docker_config(
+            content,
+            &[("123456789012.dkr.ecr.eu-west-1.amazonaws.com", "bmV3")],
+        )
+        .unwrap();
+        let document: Value = serde_json::from_str(&merged).unwrap();
+
+        assert_eq!(document["auths"]["ghcr.io"]["auth"], "Z2g=");
+        assert_eq!(
+            document["auths"]["123456789012.dkr.ecr.eu-west-1.amazonaws.com"]["auth"],
+            "bmV3"
+        );
+        assert_eq!(document["credHelpers"]["gcr.io"], "gcloud");
+        assert!(merge_docker_config("", &[("r", "a")]).is_ok());
+    }
+
+    #[test]
+    fn test_merge_docker_config_rejects_unexpected_shapes() {
+        for content in [r#"[]"#, r#"{"auths":[]}"#, r#"{"auths":{"r":"a"}}"#] {
+            let err = merge_docker_config(content, &[("r", "bmV3")]).unwrap_err();
+            assert!(
+                err.to_string().contains("is not an object"),
+                "{content}: {err}"
+            );
+        }
+    }
+}
@@ -18,6 +18,13 @@ struct K8sExecCredentialStatus {
 pub struct CacheManager {
     cache_dir: PathBuf,
     cache_path: PathBuf,
+    refresh_buffer: Duration,
+}
+
+/// Cached tokens are refreshed `refresh_buffer` ahead of their expiry, so kubectl never
+/// receives a token that expires mid request.
+fn is_fresh(expiration: DateTime<Utc>, now: DateTime<Utc>, refresh_buffer: Duration) -> bool {
+    now + refresh_buffer < expiration
 }
 
 pub struct CacheManagerInputs<'a> {
@@ -25,17 +32,28 @@ pub struct CacheManagerInputs<'a> {
     pub role: &'a str,
     pub region: &'a Region,
     pub cluster: &'a str,
+    pub api_version: &'a str,
+    pub sts_url: &'a str,
+    pub expires_in: &'a Duration,
+    pub refresh_buffer: Duration,
     pub cache_dir: &'a Path,
 }
 
 impl CacheManager {
     pub fn new(args: &CacheManagerInputs) -> Self {
         let cache_file_name = format!(
-            "eks-{account}-{role}-{region}-{cluster}",
+            "eks-{account}-{role}-{region}-{cluster}-{version}-{sts_host}-{expires_in}s",
             account = &args.account_id,
             role = &args.role,
             region = &args.region,
-            cluster = &args.cluster
+            cluster = &args.cluster,
+            // Only the version part, the group contains path separators
+            version = args.api_version.rsplit('/').next().unwrap_or_default(),
+            sts_host = args
+                .sts_url
+                .trim_start_matches("https://")
+                .replace(['/', ':'], "_"),
+            expires_in = args.expires_in.num_seconds()
         );
 
         let mut cache_path = PathBuf::new();
@@ -45,25 +63,20 @@ impl CacheManager {
         Self {
             cache_dir: args.cache_dir.to_path_buf(),
             cache_path,
+            refresh_buffer: args.refresh_buffer,
         }
     }
 
-    pub fn resolve_cache_hit(&self) -> Option<String> {
-        fs::read_to_string(&self.cache_path)
-            .ok()
-            .and_then(|content| {
-                serde_json::from_str::<K8sExecCredential>(&content)
-                    .ok()
-                    .and_then(|k8s_exec_creds| {
-                        if Utc::now() + Duration::seconds(30)
-                            < k8s_exec_creds.status.expiration_timestamp
-                        {
-                            Some(content)
-                        } else {
-                            None
-                        }
-                    })
-            })
+    /// Returns the cached credentials when they stay valid for longer than the refresh buffer.
+    pub fn resolve_cache_hit(&self, now: DateTime<Utc>) -> Option<String> {
+        let content = fs::read_to_string(&self.cache_path).ok()?;
+        let k8s_exec_creds = serde_json::from_str::<K8sExecCredential>(&content).ok()?;
+        is_fresh(
+            k8s_exec_creds.status.expiration_timestamp,
+            now,
+            self.refresh_buffer,
+        )
+        .then_some(content)
     }
 
     pub fn cache_credentials(&self, creds: &str) -> Result<(), std::io::Error> {
@@ -71,3 +84,41 @@ impl CacheManager {
         fs::write(&self.cache_path, creds)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_honours_refresh_buffer() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("aws-auth-eks-cache-{}", std::process::id()));
+        let now = "2030-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cache_manager = CacheManager::new(&CacheManagerInputs {
+            account_id: "123456789012",
+            role: "Admin",
+            region: &Region::new("eu-west-1"),
+            cluster: "main",
+            api_version: "client.authentication.k8s.io/v1",
+            sts_url: "https://sts.eu-west-1.amazonaws.com",
+            expires_in: &Duration::seconds(600),
+            refresh_buffer: Duration::seconds(60),
+            cache_dir: &cache_dir,
+        });
+        assert!(cache_manager.cache_path.ends_with(
+            "eks-123456789012-Admin-eu-west-1-main-v1-sts.eu-west-1.amazonaws.com-600s"
+        ));
+
+        cache_manager
+            .cache_credentials(r#"{"status":{"expirationTimestamp":"2030-01-01T00:02:00Z"}}"#)
+            .unwrap();
+        assert!(cache_manager.resolve_cache_hit(now).is_some());
+        assert!(cache_manager
+            .resolve_cache_hit(now + Duration::seconds(59))
+            .is_some());
+        assert!(cache_manager
+            .resolve_cache_hit(now + Duration::seconds(60))
+            .is_none());
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+}
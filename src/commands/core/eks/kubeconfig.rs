@@ -0,0 +1,279 @@
+use crate::aws_sso::AwsSsoManagerError;
+use crate::utils::{
+    atomic_write, region,
+    signed_request::{self, send_signed_request, SignedRequest},
+};
+use aws_config::Region;
+use aws_sdk_ssooidc::config::Credentials;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub struct ExecKubeconfigInputs<'a> {
+    pub cluster: &'a str,
+    pub region: Region,
+    pub kubeconfig: &'a Path,
+    pub context_name: &'a str,
+    pub server: Option<&'a str>,
+    pub certificate_authority_data: Option<&'a str>,
+    pub eks_endpoint: Option<&'a str>,
+    pub exec_command: &'a str,
+    pub api_version: &'a str,
+    pub exec_args: Vec<String>,
+    pub set_current_context: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error resolving SSO credentials: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("Error describing cluster: {0}")]
+    DescribeCluster(#[from] signed_request::Error),
+    #[error("Invalid DescribeCluster response: {0}")]
+    InvalidDescribeCluster(#[from] serde_json::Error),
+    #[error("Cluster {0} has no {1} yet, is it still being created?")]
+    IncompleteCluster(String, &'static str),
+    #[error("Error accessing kubeconfig {0:?}: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("Invalid kubeconfig {0:?}: {1}")]
+    InvalidKubeconfig(PathBuf, String),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Deserialize)]
+struct DescribeClusterResponse {
+    cluster: ClusterDescription,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClusterDescription {
+    endpoint: Option<String>,
+    certificate_authority: Option<CertificateAuthority>,
+}
+
+#[derive(Deserialize)]
+struct CertificateAuthority {
+    data: Option<String>,
+}
+
+/// Returns the kubeconfig file to write, the first path of $KUBECONFIG like kubectl, otherwise ~/.kube/config.
+pub fn default_kubeconfig() -> PathBuf {
+    std::env::var_os("KUBECONFIG")
+        .and_then(|paths| std::env::split_paths(&paths).find(|p| !p.as_os_str().is_empty()))
+        .unwrap_or_else(|| {
+            home::home_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join(".kube")
+                .join("config")
+        })
+}
+
+async fn describe_cluster(
+    credentials: &Credentials,
+    endpoint: &str,
+    region: &str,
+    cluster: &str,
+) -> Result<(String, String)> {
+    let url = format!("{}/clusters/{cluster}", endpoint.trim_end_matches('/'));
+    let body = send_signed_request(
+        credentials,
+        SignedRequest {
+            method: "GET",
+            url: &url,
+            region,
+            service: "eks",
+            headers: &[("accept", "application/json")],
+            body: &[],
+        },
+    )
+    .await?;
+    let description = serde_json::from_str::<DescribeClusterResponse>(&body)?.cluster;
+    let endpoint = description
+        .endpoint
+        .ok_or_else(|| Error::IncompleteCluster(cluster.to_string(), "endpoint"))?;
+    let ca_data = description
+        .certificate_authority
+        .and_then(|ca| ca.data)
+        .ok_or_else(|| Error::IncompleteCluster(cluster.to_string(), "certificate authority"))?;
+    Ok((endpoint, ca_data))
+}
+
+/// Replaces the entry with the same name in the named list, appending it when missing.
+fn upsert_named(document: &mut Mapping, list: &str, name: &str, field: &str, value: Value) {
+    let mut entry = Mapping::new();
+    entry.insert("name".into(), name.into());
+    entry.insert(field.into(), value);
+    let entry = Value::Mapping(entry);
+
+    let entries = document
+        .entry(list.into())
+        .or_insert_with(|| Value::Sequence(Vec::new()));
+    if !entries.is_sequence() {
+        *entries = Value::Sequence(Vec::new());
+    }
+    let entries = entries.as_sequence_mut().expect("entries is a sequence");
+    match entries
+        .iter_mut()
+        .find(|existing| existing.get("name").and_then(Value::as_str) == Some(name))
+    {
+        Some(existing) => *existing = entry,
+        None => entries.push(entry),
+    }
+}
+
+fn merge_kubeconfig(
+    content: &str,
+    exec_inputs: &ExecKubeconfigInputs,
+    server: &str,
+    ca_data: &str,
+) -> std::result::Result<String, serde_yaml::Error> {
+    let mut document = match serde_yaml::from_str::<Value>(content)? {
+        Value::Mapping(mapping) => mapping,
+        _ => Mapping::new(),
+    };
+    for (key, value) in [("apiVersion", "v1"), ("kind", "Config")] {
+        document.entry(key.into()).or_insert_with(|| value.into());
+    }
+    let name = exec_inputs.context_name;
+
+    let mut cluster = Mapping::new();
+    cluster.insert("server".into(), server.into());
+    cluster.insert("certificate-authority-data".into(), ca_data.into());
+    upsert_named(&mut document, "clusters", name, "cluster", cluster.into());
+
+    let mut exec = Mapping::new();
+    exec.insert("apiVersion".into(), exec_inputs.api_version.into());
+    exec.insert("command".into(), exec_inputs.exec_command.into());
+    exec.insert(
+        "args".into(),
+        Value::Sequence(
+            exec_inputs
+                .exec_args
+                .iter()
+                .map(|arg| arg.as_str().into())
+                .collect(),
+        ),
+    );
+    exec.insert("interactiveMode".into(), "Never".into());
+    let mut user = Mapping::new();
+    user.insert("exec".into(), exec.into());
+    upsert_named(&mut document, "users", name, "user", user.into());
+
+    let mut context = Mapping::new();
+    context.insert("cluster".into(), name.into());
+    context.insert("user".into(), name.into());
+    upsert_named(&mut document, "contexts", name, "context", context.into());
+
+    if exec_inputs.set_current_context {
+        document.insert("current-context".into(), name.into());
+    }
+    serde_yaml::to_string(&Value::Mapping(document))
+}
+
+/// Writes the cluster, user and context to the kubeconfig. The cluster endpoint and CA are
+/// taken from eks:DescribeCluster unless both are supplied.
+pub async fn exec_kubeconfig<F>(
+    mut credential_resolver: F,
+    exec_inputs: ExecKubeconfigInputs<'_>,
+) -> Result
+where
+    F: AsyncFnMut() -> std::result::Result<Credentials, AwsSsoManagerError>,
+{
+    let (server, ca_data) = match (exec_inputs.server, exec_inputs.certificate_authority_data) {
+        (Some(server), Some(ca_data)) => (server.to_string(), ca_data.to_string()),
+        (server, ca_data) => {
+            let credentials = credential_resolver().await?;
+            let region = exec_inputs.region.as_ref();
+            let endpoint = exec_inputs
+                .eks_endpoint
+                .map(str::to_string)
+                .unwrap_or_else(|| {
+                    let dns_suffix =
+                        region::find_partition(region).map_or("amazonaws.com", |p| p.dns_suffix);
+                    format!("https://eks.{region}.{dns_suffix}")
+                });
+            let (described_server, described_ca_data) =
+                describe_cluster(&credentials, &endpoint, region, exec_inputs.cluster).await?;
+            (
+                server.map_or(described_server, str::to_string),
+                ca_data.map_or(described_ca_data, str::to_string),
+            )
+        }
+    };
+
+    let path = exec_inputs.kubeconfig;
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(Error::Io(path.to_path_buf(), err)),
+    };
+    let merged = merge_kubeconfig(&content, &exec_inputs, &server, &ca_data)
+        .map_err(|err| Error::InvalidKubeconfig(path.to_path_buf(), err.to_string()))?;
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|err| Error::Io(parent.to_path_buf(), err))?;
+    }
+    atomic_write(path, &merged).map_err(|err| Error::Io(path.to_path_buf(), err))?;
+
+    println!(
+        "INFO: Wrote context {} for cluster {} to {}{}",
+        exec_inputs.context_name,
+        exec_inputs.cluster,
+        path.display(),
+        if exec_inputs.set_current_context {
+            ", set as current context"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::utils::render_placeholders;
+
+    #[test]
+    fn test_merge_kubeconfig_replaces_existing_entries() {
+        let content = "apiVersion: v1\nkind: Config\ncurrent-context: other\nclusters:\n- name: other\n  cluster:\n    server: https://other\n- name: dev-main\n  cluster:\n    server: https://old\ncontexts: []\nusers: []\n";
+        let context_name = render_placeholders(
+            "{alias}-{cluster}",
+            &[("alias", "dev"), ("cluster", "main")],
+        );
+        let inputs = ExecKubeconfigInputs {
+            cluster: "main",
+            region: Region::new("eu-west-1"),
+            kubeconfig: Path::new("config"),
+            context_name: &context_name,
+            server: None,
+            certificate_authority_data: None,
+            eks_endpoint: None,
+            exec_command: "aws-auth",
+            api_version: "client.authentication.k8s.io/v1",
+            exec_args: vec!["eks".into(), "-A".into(), "dev".into()],
+            set_current_context: true,
+        };
+        let merged = merge_kubeconfig(content, &inputs, "https://new", "Q0E=").unwrap();
+        let document: Value = serde_yaml::from_str(&merged).unwrap();
+
+        assert_eq!(document["current-context"], "dev-main");
+        let clusters = document["clusters"].as_sequence().unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0]["cluster"]["server"], "https://other");
+        assert_eq!(clusters[1]["cluster"]["server"], "https://new");
+        assert_eq!(document["contexts"][0]["context"]["user"], "dev-main");
+        let exec = &document["users"][0]["user"]["exec"];
+        assert_eq!(exec["command"], "aws-auth");
+        assert_eq!(exec["args"][2], "dev");
+    }
+}
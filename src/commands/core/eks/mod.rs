@@ -1,13 +1,22 @@
 mod cache;
+pub mod kubeconfig;
+pub mod prefetch;
 mod sign;
+pub mod token;
 
 use crate::aws_sso::AwsSsoManagerError;
+use crate::cmd::EksTokenArgs;
 use aws_config::Region;
 use aws_sdk_ssooidc::config::Credentials;
-use cache::CacheManagerInputs;
-use chrono::TimeDelta;
+use cache::{CacheManager, CacheManagerInputs};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Deserialize;
+pub use sign::{DEFAULT_EXEC_CREDENTIALS_API_VERSION, SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS};
 use std::path::Path;
 
+// Set by kubectl for exec plugins, describes the expected ExecCredential
+const KUBERNETES_EXEC_INFO_ENV: &str = "KUBERNETES_EXEC_INFO";
+
 pub struct ExecEksInputs<'a> {
     pub account: &'a str,
     pub role: &'a str,
@@ -16,6 +25,37 @@ pub struct ExecEksInputs<'a> {
     pub eks_cache_dir: Option<&'a Path>,
     pub config_dir: &'a Path,
     pub expiry: Option<TimeDelta>,
+    pub refresh_buffer: TimeDelta,
+    pub api_version: Option<&'a str>,
+    pub sts_endpoint: Option<&'a str>,
+    pub fips: bool,
+}
+
+impl<'a> ExecEksInputs<'a> {
+    pub fn new(
+        account: &'a str,
+        role: &'a str,
+        cluster: &'a str,
+        region: Region,
+        config_dir: &'a Path,
+        token: &'a EksTokenArgs,
+    ) -> Self {
+        Self {
+            account,
+            role,
+            cluster,
+            region,
+            eks_cache_dir: token.eks_cache_dir.as_deref(),
+            config_dir,
+            expiry: token
+                .eks_expiry_seconds
+                .map(|v| TimeDelta::seconds(v as i64)),
+            refresh_buffer: TimeDelta::seconds(token.eks_refresh_buffer_seconds as i64),
+            api_version: token.api_version.as_deref(),
+            sts_endpoint: token.sts_endpoint.as_deref(),
+            fips: token.fips,
+        }
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -28,6 +68,10 @@ pub enum Error {
     Cache(#[from] std::io::Error),
     #[error("Invalid credential json: {0}")]
     Serde(#[from] serde_json::Error),
+    #[error("Invalid {KUBERNETES_EXEC_INFO_ENV}: {0}")]
+    InvalidExecInfo(serde_json::Error),
+    #[error("Unsupported ExecCredential apiVersion {0}, supported: {1}")]
+    UnsupportedApiVersion(String, String),
 }
 
 impl From<AwsSsoManagerError> for Error {
@@ -36,41 +80,177 @@ impl From<AwsSsoManagerError> for Error {
     }
 }
 
-pub type Result = std::result::Result<(), Error>;
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Deserialize)]
+struct ExecInfo {
+    #[serde(rename = "apiVersion")]
+    api_version: Option<String>,
+}
+
+/// Resolves the ExecCredential apiVersion to answer with, the explicit version wins over
+/// the one kubectl expects in KUBERNETES_EXEC_INFO.
+fn resolve_api_version(explicit: Option<&str>, exec_info: Option<&str>) -> Result<String> {
+    let api_version = match (explicit, exec_info) {
+        (Some(api_version), _) => Some(api_version.to_string()),
+        (None, Some(exec_info)) => {
+            serde_json::from_str::<ExecInfo>(exec_info)
+                .map_err(Error::InvalidExecInfo)?
+                .api_version
+        }
+        (None, None) => None,
+    }
+    .unwrap_or_else(|| DEFAULT_EXEC_CREDENTIALS_API_VERSION.to_string());
+
+    if !SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS.contains(&api_version.as_str()) {
+        return Err(Error::UnsupportedApiVersion(
+            api_version,
+            SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS.join(", "),
+        ));
+    }
+    Ok(api_version)
+}
+
+/// True when the STS session ends before a token signed now would, the token would then be
+/// cut short so the session is refreshed before signing.
+fn session_near_expiry(
+    session_expiry: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    expires_in: &TimeDelta,
+) -> bool {
+    session_expiry.is_some_and(|session_expiry| session_expiry < now + *expires_in)
+}
+
+/// Signing parameters and cache entry of one EKS token
+struct TokenRequest {
+    cluster: String,
+    api_version: String,
+    sts_endpoint: sign::StsEndpoint,
+    expires_in: TimeDelta,
+    cache_manager: CacheManager,
+}
+
+impl TokenRequest {
+    fn new(exec_inputs: &ExecEksInputs, exec_info: Option<&str>) -> Result<Self> {
+        let expires_in = exec_inputs.expiry.unwrap_or(sign::DEFAULT_EXPIRTY);
+        let api_version = resolve_api_version(exec_inputs.api_version, exec_info)?;
+        let sts_endpoint = sign::resolve_sts_endpoint(
+            exec_inputs.region.as_ref(),
+            exec_inputs.fips,
+            exec_inputs.sts_endpoint,
+        )?;
+        let cache_manager = CacheManager::new(&CacheManagerInputs {
+            account_id: exec_inputs.account,
+            role: exec_inputs.role,
+            cluster: exec_inputs.cluster,
+            api_version: &api_version,
+            sts_url: &sts_endpoint.url,
+            expires_in: &expires_in,
+            refresh_buffer: exec_inputs.refresh_buffer,
+            region: &exec_inputs.region,
+            cache_dir: &exec_inputs
+                .eks_cache_dir
+                .unwrap_or(exec_inputs.config_dir)
+                .join("eks"),
+        });
+        Ok(Self {
+            cluster: exec_inputs.cluster.to_string(),
+            api_version,
+            sts_endpoint,
+            expires_in,
+            cache_manager,
+        })
+    }
+
+    fn sign_and_cache(&self, credentials: &Credentials, now: DateTime<Utc>) -> Result<String> {
+        let k8s_creds = sign::generate_eks_credentials(
+            credentials,
+            &self.sts_endpoint,
+            &self.cluster,
+            &self.expires_in,
+            &self.api_version,
+            now,
+        )?;
+        let string_creds = serde_json::to_string(&k8s_creds)?;
+        self.cache_manager.cache_credentials(&string_creds)?;
+        Ok(string_creds)
+    }
+}
 
+/// Prints the exec credentials, signing a new token when the cached one is missing or about
+/// to expire. The resolver receives true when the STS session must be refreshed.
 pub async fn exec_eks<F>(mut credential_resolver: F, exec_inputs: ExecEksInputs<'_>) -> Result
 where
-    F: AsyncFnMut() -> std::result::Result<Credentials, AwsSsoManagerError>,
+    F: AsyncFnMut(bool) -> std::result::Result<Credentials, AwsSsoManagerError>,
 {
-    let cache_manager = cache::CacheManager::new(&CacheManagerInputs {
-        account_id: exec_inputs.account,
-        role: exec_inputs.role,
-        cluster: exec_inputs.cluster,
-        region: &exec_inputs.region,
-        cache_dir: &exec_inputs
-            .eks_cache_dir
-            .unwrap_or(exec_inputs.config_dir)
-            .join("eks"),
-    });
-
-    let exec_creds = if let Some(hit) = cache_manager.resolve_cache_hit() {
+    let token_request = TokenRequest::new(
+        &exec_inputs,
+        std::env::var(KUBERNETES_EXEC_INFO_ENV).ok().as_deref(),
+    )?;
+
+    let now = Utc::now();
+    let exec_creds = if let Some(hit) = token_request.cache_manager.resolve_cache_hit(now) {
         hit
     } else {
-        let credentials = credential_resolver().await?;
-
-        let k8s_creds = sign::generate_eks_credentials(
-            &credentials,
-            &exec_inputs.region,
-            exec_inputs.cluster,
-            exec_inputs.expiry.as_ref(),
-        )?;
-
-        let string_creds = serde_json::to_string(&k8s_creds)?;
-        cache_manager.cache_credentials(&string_creds)?;
-        string_creds
+        // Resolving may run the interactive SSO login, the clock is read again once it returned
+        // so the token is not signed, nor cached, with a stale date
+        let mut credentials = credential_resolver(false).await?;
+        if session_near_expiry(
+            credentials.expiry().map(Into::into),
+            Utc::now(),
+            &token_request.expires_in,
+        ) {
+            credentials = credential_resolver(true).await?;
+        }
+        token_request.sign_and_cache(&credentials, Utc::now())?
     };
 
     println!("{}", exec_creds);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_near_expiry() {
+        let now = "2030-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let expires_in = TimeDelta::seconds(860);
+        assert!(!session_near_expiry(None, now, &expires_in));
+        assert!(session_near_expiry(
+            Some(now + TimeDelta::seconds(300)),
+            now,
+            &expires_in
+        ));
+        assert!(!session_near_expiry(
+            Some(now + TimeDelta::hours(1)),
+            now,
+            &expires_in
+        ));
+    }
+
+    #[test]
+    fn test_resolve_api_version_from_exec_info() {
+        let exec_info = r#"{"kind":"ExecCredential","apiVersion":"client.authentication.k8s.io/v1","spec":{"interactive":false}}"#;
+        assert_eq!(
+            resolve_api_version(None, Some(exec_info)).unwrap(),
+            "client.authentication.k8s.io/v1"
+        );
+        assert_eq!(
+            resolve_api_version(Some(DEFAULT_EXEC_CREDENTIALS_API_VERSION), Some(exec_info))
+                .unwrap(),
+            DEFAULT_EXEC_CREDENTIALS_API_VERSION
+        );
+        assert_eq!(
+            resolve_api_version(None, None).unwrap(),
+            DEFAULT_EXEC_CREDENTIALS_API_VERSION
+        );
+        assert!(resolve_api_version(
+            None,
+            Some(r#"{"apiVersion":"client.authentication.k8s.io/v1alpha1"}"#)
+        )
+        .is_err());
+    }
+}
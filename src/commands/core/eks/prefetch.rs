@@ -0,0 +1,381 @@
+use super::{session_near_expiry, ExecEksInputs, TokenRequest};
+use crate::alias_providers::{self, AliasProviderError, ProvideAliases};
+use crate::aws_sso::{
+    build_sso_mgr_cached, config::AwsSsoConfig, AwsSsoConfigError, AwsSsoManagerError,
+};
+use crate::cmd::{Cli, Commands, CoreCommands, EksTokenArgs};
+use crate::utils::{region::resolve_region, resolve_assume_identifier, resolve_config_dir};
+use aws_config::Region;
+use aws_sdk_ssooidc::config::Credentials;
+use chrono::Utc;
+use clap::Parser;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub struct ExecPrefetchInputs<'a> {
+    pub targets: &'a [String],
+    pub kubeconfig: Option<&'a Path>,
+    pub config_dir: &'a Path,
+    pub sso_cache_dir: Option<&'a Path>,
+    pub team_aliases: Option<&'a Path>,
+    pub ignore_cache: bool,
+    pub refresh_sts_token: bool,
+    pub region: Option<&'a str>,
+    pub parallel: usize,
+    pub token: &'a EksTokenArgs,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error getting alias: {0}")]
+    AliasProvider(#[from] AliasProviderError),
+    #[error("Error resolving SSO credentials: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("Invalid target {0:?}, expected ALIAS:CLUSTER")]
+    InvalidTarget(String),
+    #[error("Alias {0} not found")]
+    AliasNotFound(String),
+    #[error("Error resolving region: {0}")]
+    Region(String),
+    #[error("Error reading kubeconfig {0:?}: {1}")]
+    Kubeconfig(PathBuf, String),
+    #[error("No kubeconfig context executes aws-auth eks")]
+    NoTargets,
+    #[error("{0} of {1} targets failed")]
+    Failed(usize, usize),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+/// Cluster to prefetch, owning everything needed to build its ExecEksInputs
+struct PrefetchTarget {
+    label: String,
+    account: String,
+    role: String,
+    cluster: String,
+    region: String,
+    token: EksTokenArgs,
+}
+
+impl PrefetchTarget {
+    fn exec_inputs<'a>(&'a self, config_dir: &'a Path) -> ExecEksInputs<'a> {
+        ExecEksInputs::new(
+            &self.account,
+            &self.role,
+            &self.cluster,
+            Region::new(self.region.clone()),
+            config_dir,
+            &self.token,
+        )
+    }
+}
+
+/// Kubeconfig context whose user executes `aws-auth eks`
+struct KubeconfigExecContext {
+    context: String,
+    args: Vec<String>,
+    // ExecCredential apiVersion kubectl will request
+    api_version: Option<String>,
+}
+
+/// Returns every kubeconfig context whose user executes aws-auth eks.
+fn kubeconfig_exec_contexts(content: &str) -> serde_yaml::Result<Vec<KubeconfigExecContext>> {
+    let document: Value = serde_yaml::from_str(content)?;
+    let named = |list: &str, name: &str| {
+        document[list]
+            .as_sequence()
+            .and_then(|entries| {
+                entries
+                    .iter()
+                    .find(|entry| entry["name"].as_str() == Some(name))
+            })
+            .cloned()
+    };
+    let contexts = document["contexts"]
+        .as_sequence()
+        .cloned()
+        .unwrap_or_default();
+    Ok(contexts
+        .iter()
+        .filter_map(|context| {
+            let name = context["name"].as_str()?;
+            let user = named("users", context["context"]["user"].as_str()?)?;
+            let exec = &user["user"]["exec"];
+            let command = Path::new(exec["command"].as_str()?).file_stem()?;
+            let args: Vec<String> = exec["args"]
+                .as_sequence()?
+                .iter()
+                .filter_map(|arg| arg.as_str().map(str::to_string))
+                .collect();
+            (command == "aws-auth" && args.first().is_some_and(|arg| arg == "eks")).then(|| {
+                KubeconfigExecContext {
+                    context: name.to_string(),
+                    args,
+                    api_version: exec["apiVersion"].as_str().map(str::to_string),
+                }
+            })
+        })
+        .collect())
+}
+
+/// Fills the EKS token cache of every target. Credentials of all targets are resolved with one
+/// SSO login, concurrently, then a token is signed per cluster.
+pub async fn exec_prefetch(exec_inputs: ExecPrefetchInputs<'_>) -> Result {
+    let config_dir = exec_inputs.config_dir;
+    let (settings, sources) = AwsSsoConfig::load_effective_config_with_sources(config_dir)?;
+    let (pinned_region, default_region) = settings.configured_regions(&sources);
+    let mut alias_provider =
+        alias_providers::build_alias_provider(config_dir, exec_inputs.team_aliases)?;
+    alias_provider.load_aliases()?;
+
+    let mut targets = Vec::new();
+    for target in exec_inputs.targets {
+        let (alias, cluster) = target
+            .split_once(':')
+            .filter(|(alias, cluster)| !alias.is_empty() && !cluster.is_empty())
+            .ok_or_else(|| Error::InvalidTarget(target.clone()))?;
+        let identity = alias_provider
+            .get_alias(alias)?
+            .ok_or_else(|| Error::AliasNotFound(alias.to_string()))?;
+        let region = resolve_region(
+            exec_inputs.region,
+            identity.region,
+            pinned_region,
+            default_region,
+            &settings.sso_region,
+        )
+        .map_err(Error::Region)?;
+        targets.push(PrefetchTarget {
+            label: target.clone(),
+            account: identity.account.to_string(),
+            role: identity.role.to_string(),
+            cluster: cluster.to_string(),
+            region,
+            token: exec_inputs.token.clone(),
+        });
+    }
+
+    if let Some(kubeconfig) = exec_inputs.kubeconfig {
+        let content = std::fs::read_to_string(kubeconfig)
+            .map_err(|err| Error::Kubeconfig(kubeconfig.to_path_buf(), err.to_string()))?;
+        let contexts = kubeconfig_exec_contexts(&content)
+            .map_err(|err| Error::Kubeconfig(kubeconfig.to_path_buf(), err.to_string()))?;
+        if contexts.is_empty() && targets.is_empty() {
+            return Err(Error::NoTargets);
+        }
+        for KubeconfigExecContext {
+            context,
+            args,
+            api_version: exec_api_version,
+        } in contexts
+        {
+            let cli = match Cli::try_parse_from(std::iter::once("aws-auth".to_string()).chain(args))
+            {
+                Ok(cli) => cli,
+                Err(err) => {
+                    eprintln!(
+                        "WARNING: Skipping context {context}, invalid aws-auth arguments: {err}"
+                    );
+                    continue;
+                }
+            };
+            let Commands::Core(CoreCommands::Eks {
+                command: None,
+                common,
+                cluster,
+                mut token,
+            }) = cli.command
+            else {
+                continue;
+            };
+            if resolve_config_dir(common.config_dir.as_deref()) != config_dir {
+                eprintln!("WARNING: Skipping context {context}, it uses another config dir, prefetch it with --config-dir");
+                continue;
+            }
+            let Some(cluster) = cluster.or(settings.eks_cluster.clone()) else {
+                eprintln!("WARNING: Skipping context {context}, no cluster configured");
+                continue;
+            };
+            let (account, role, alias_region) = match resolve_assume_identifier(
+                &mut alias_provider,
+                &common,
+                settings.default_alias.as_deref(),
+            ) {
+                Ok(identity) => (
+                    identity.account.to_string(),
+                    identity.role.to_string(),
+                    identity.region.map(str::to_string),
+                ),
+                Err(err) => {
+                    eprintln!("WARNING: Skipping context {context}: {err}");
+                    continue;
+                }
+            };
+            let region = resolve_region(
+                common.region.as_deref(),
+                alias_region.as_deref(),
+                pinned_region,
+                default_region,
+                &settings.sso_region,
+            )
+            .map_err(Error::Region)?;
+            token.api_version = token.api_version.or(exec_api_version);
+            targets.push(PrefetchTarget {
+                label: context,
+                account,
+                role,
+                cluster,
+                region,
+                token,
+            });
+        }
+    }
+
+    let now = Utc::now();
+    let mut failed = 0;
+    let mut pending = Vec::new();
+    for target in &targets {
+        match TokenRequest::new(&target.exec_inputs(config_dir), None) {
+            Ok(request) if request.cache_manager.resolve_cache_hit(now).is_some() => {
+                println!("INFO: {} already cached", target.label);
+            }
+            Ok(request) => pending.push((target, request)),
+            Err(err) => {
+                eprintln!("ERROR: {}: {err}", target.label);
+                failed += 1;
+            }
+        }
+    }
+
+    if !pending.is_empty() {
+        let mut account_roles = pending
+            .iter()
+            .map(|(target, _)| (target.account.clone(), target.role.clone()))
+            .collect::<Vec<_>>();
+        account_roles.sort();
+        account_roles.dedup();
+
+        let mut sso_manager = build_sso_mgr_cached(config_dir, exec_inputs.sso_cache_dir)?;
+        let mut credentials: HashMap<(String, String), std::result::Result<Credentials, String>> =
+            account_roles
+                .iter()
+                .cloned()
+                .zip(
+                    sso_manager
+                        .assume_roles(
+                            &account_roles,
+                            exec_inputs.parallel,
+                            exec_inputs.refresh_sts_token,
+                            exec_inputs.ignore_cache,
+                        )
+                        .await?
+                        .into_iter()
+                        .map(|result| result.map_err(|err| err.to_string())),
+                )
+                .collect();
+
+        // Assuming may run the interactive SSO login, the clock is read again once it returned
+        let resolved_at = Utc::now();
+        // Sessions ending before a fresh token would are refreshed once, like `aws-auth eks` does
+        let mut near_expiry = pending
+            .iter()
+            .filter(|(target, request)| {
+                credentials
+                    .get(&(target.account.clone(), target.role.clone()))
+                    .and_then(|result| result.as_ref().ok())
+                    .is_some_and(|credentials| {
+                        session_near_expiry(
+                            credentials.expiry().map(Into::into),
+                            resolved_at,
+                            &request.expires_in,
+                        )
+                    })
+            })
+            .map(|(target, _)| (target.account.clone(), target.role.clone()))
+            .collect::<Vec<_>>();
+        near_expiry.sort();
+        near_expiry.dedup();
+        if !near_expiry.is_empty() {
+            let refreshed = sso_manager
+                .assume_roles(&near_expiry, exec_inputs.parallel, true, false)
+                .await?;
+            for (account_role, result) in near_expiry.into_iter().zip(refreshed) {
+                credentials.insert(account_role, result.map_err(|err| err.to_string()));
+            }
+        }
+
+        let signed_at = Utc::now();
+        for (target, request) in pending {
+            let result = credentials
+                .get(&(target.account.clone(), target.role.clone()))
+                .expect("Credentials are resolved for every pending target")
+                .as_ref()
+                .map_err(String::clone)
+                .and_then(|credentials| {
+                    request
+                        .sign_and_cache(credentials, signed_at)
+                        .map_err(|err| err.to_string())
+                });
+            match result {
+                Ok(_) => println!(
+                    "INFO: Prefetched {} ({}/{} cluster {} in {})",
+                    target.label, target.account, target.role, target.cluster, target.region
+                ),
+                Err(err) => {
+                    eprintln!("ERROR: {}: {}", target.label, err.trim_end());
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::Failed(failed, targets.len()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kubeconfig_exec_contexts_selects_aws_auth_users() {
+        let content = r#"
+contexts:
+- name: dev-main
+  context: {cluster: dev-main, user: dev-main}
+- name: other
+  context: {cluster: other, user: other}
+users:
+- name: dev-main
+  user:
+    exec:
+      apiVersion: client.authentication.k8s.io/v1
+      command: /usr/local/bin/aws-auth
+      args: [eks, -A, dev, -c, main]
+- name: other
+  user:
+    exec:
+      command: aws
+      args: [eks, get-token, --cluster-name, other]
+"#;
+        let contexts = kubeconfig_exec_contexts(content).unwrap();
+        assert_eq!(contexts.len(), 1);
+        assert_eq!(contexts[0].context, "dev-main");
+        assert_eq!(contexts[0].args, ["eks", "-A", "dev", "-c", "main"]);
+        assert_eq!(
+            contexts[0].api_version.as_deref(),
+            Some("client.authentication.k8s.io/v1")
+        );
+    }
+}
@@ -1,4 +1,3 @@
-use aws_config::Region;
 use aws_sdk_ssooidc::config::Credentials;
 use aws_sigv4::http_request::{
     self, SignableRequest, SignatureLocation, SigningError, SigningParams, SigningSettings,
@@ -6,15 +5,17 @@ use aws_sigv4::http_request::{
 use aws_sigv4::sign;
 use aws_smithy_runtime_api::client::identity::Identity;
 use base64::{engine::general_purpose::URL_SAFE, Engine};
+use chrono::Duration;
 use chrono::{DateTime, Utc};
-use chrono::{Duration, Local};
 use http::Request;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-const K8S_AWS_ID_HEADER: &str = "x-k8s-aws-id";
-const TOKEN_PREFIX: &str = "k8s-aws-v1";
-const DEFAULT_EXPIRTY: Duration = Duration::seconds(860);
+use crate::utils::region;
+
+pub const K8S_AWS_ID_HEADER: &str = "x-k8s-aws-id";
+pub const TOKEN_PREFIX: &str = "k8s-aws-v1";
+pub const DEFAULT_EXPIRTY: Duration = Duration::seconds(860);
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -22,10 +23,19 @@ pub enum Error {
     FailedToSign(#[from] SigningError),
     #[error("Invalid EKS Auth request parameters: {0}")]
     InvalidRequest(#[from] http::Error),
+    #[error("FIPS STS endpoints are not available in the {0} partition")]
+    FipsUnsupported(&'static str),
+    #[error("Invalid STS endpoint {0:?}, expected an https URL")]
+    InvalidEndpoint(String),
 }
 
 pub const DEFAULT_EXEC_CREDENTIALS_KIND: &str = "ExecCredential";
 pub const DEFAULT_EXEC_CREDENTIALS_API_VERSION: &str = "client.authentication.k8s.io/v1beta1";
+/// ExecCredential versions understood by kubectl that `aws-auth eks` can answer with
+pub const SUPPORTED_EXEC_CREDENTIALS_API_VERSIONS: [&str; 2] = [
+    "client.authentication.k8s.io/v1",
+    DEFAULT_EXEC_CREDENTIALS_API_VERSION,
+];
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct K8sExecCredentialsStatus {
@@ -45,42 +55,106 @@ pub struct K8sExecCredentials {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// STS endpoint the token is presigned for, with the region the signature must be scoped to
+#[derive(Debug, PartialEq)]
+pub struct StsEndpoint {
+    pub url: String,
+    pub signing_region: String,
+}
+
+/// Returns the region STS verifies signatures with for the host, None for hosts that are
+/// not STS service endpoints like VPC endpoints.
+pub fn sts_signing_region(host: &str) -> Option<String> {
+    let rest = host
+        .strip_prefix("sts-fips.")
+        .or_else(|| host.strip_prefix("sts."))?;
+    let (label, _) = rest.split_once('.')?;
+    if region::find_partition(label).is_some() {
+        return Some(label.to_string());
+    }
+    // The global endpoints have no region label
+    match rest {
+        "amazonaws.com" => Some("us-east-1".to_string()),
+        "amazonaws.com.cn" => Some("cn-north-1".to_string()),
+        _ => None,
+    }
+}
+
+/// Resolves the STS endpoint for the region: the override when given, otherwise the regional
+/// (or FIPS) endpoint of the region's partition.
+pub fn resolve_sts_endpoint(
+    region: &str,
+    fips: bool,
+    endpoint_override: Option<&str>,
+) -> Result<StsEndpoint> {
+    if let Some(endpoint) = endpoint_override {
+        let url = endpoint.trim_end_matches('/');
+        let host = url
+            .strip_prefix("https://")
+            .filter(|host| !host.is_empty() && !host.contains('/'))
+            .ok_or_else(|| Error::InvalidEndpoint(endpoint.to_string()))?;
+        return Ok(StsEndpoint {
+            url: url.to_string(),
+            signing_region: sts_signing_region(host).unwrap_or_else(|| region.to_string()),
+        });
+    }
+
+    let partition = region::find_partition(region);
+    let dns_suffix = partition.map_or("amazonaws.com", |p| p.dns_suffix);
+    let host = match (fips, partition.map(|p| p.id)) {
+        (true, Some(id @ "aws-cn")) => return Err(Error::FipsUnsupported(id)),
+        // GovCloud regional STS endpoints are FIPS validated already
+        (true, Some("aws-us-gov")) | (false, _) => format!("sts.{region}.{dns_suffix}"),
+        (true, _) => format!("sts-fips.{region}.{dns_suffix}"),
+    };
+    Ok(StsEndpoint {
+        url: format!("https://{host}"),
+        signing_region: region.to_string(),
+    })
+}
+
+/// Returns when a token signed at `now` expires, the presigned URL stops verifying once the
+/// credentials that signed it expire.
+pub fn token_expiry(
+    credential_expiry: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    expires_in: &Duration,
+) -> DateTime<Utc> {
+    let requested = now + *expires_in;
+    credential_expiry.map_or(requested, |credential_expiry| {
+        credential_expiry.min(requested)
+    })
+}
+
 pub fn generate_eks_credentials(
     credentials: &Credentials,
-    region: &Region,
+    endpoint: &StsEndpoint,
     cluster_name: &str,
-    expires_in: Option<&Duration>,
+    expires_in: &Duration,
+    api_version: &str,
+    now: DateTime<Utc>,
 ) -> Result<K8sExecCredentials> {
-    let expires_in = expires_in.unwrap_or(&DEFAULT_EXPIRTY);
-    let credential_expiry = credentials
-        .expiry()
-        .map_or(Utc::now() + *expires_in, |cx_st| {
-            let cx_dt: DateTime<Utc> = cx_st.into();
-            if cx_dt < Utc::now() + *expires_in {
-                cx_dt
-            } else {
-                Utc::now() + *expires_in
-            }
-        });
+    let credential_expiry = token_expiry(credentials.expiry().map(Into::into), now, expires_in);
 
     let mut settings = SigningSettings::default();
     settings.expires_in = Some(expires_in.to_std().unwrap());
     settings.signature_location = SignatureLocation::QueryParams;
 
     let identity = &Identity::from(credentials.to_owned());
-    let region = region.to_string();
 
     let params = sign::v4::SigningParams::builder()
         .identity(identity)
-        .region(&region)
+        .region(&endpoint.signing_region)
         .name("sts")
-        .time(Local::now().into())
+        .time(now.into())
         .settings(settings)
         .build()
         .expect("there should not be any build errors");
 
-    let uri =
-        format!("https://sts.{region}.amazonaws.com/?Action=GetCallerIdentity&Version=2011-06-15");
+    let uri = format!(
+        "{}/?Action=GetCallerIdentity&Version=2011-06-15",
+        endpoint.url
+    );
 
     let request = SignableRequest::new(
         "GET",
@@ -99,7 +173,7 @@ pub fn generate_eks_credentials(
 
     Ok(K8sExecCredentials {
         kind: DEFAULT_EXEC_CREDENTIALS_KIND.to_string(),
-        api_version: DEFAULT_EXEC_CREDENTIALS_API_VERSION.to_string(),
+        api_version: api_version.to_string(),
         spec: HashMap::new(),
         status: K8sExecCredentialsStatus {
             expiration_timestamp: credential_expiry,
@@ -107,3 +181,60 @@ pub fn generate_eks_credentials(
         },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_expiry_is_capped_by_credentials() {
+        let now = "2030-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let expires_in = Duration::seconds(600);
+        assert_eq!(token_expiry(None, now, &expires_in), now + expires_in);
+        assert_eq!(
+            token_expiry(Some(now + Duration::seconds(120)), now, &expires_in),
+            now + Duration::seconds(120)
+        );
+        assert_eq!(
+            token_expiry(Some(now + Duration::hours(1)), now, &expires_in),
+            now + expires_in
+        );
+    }
+
+    #[test]
+    fn test_resolve_sts_endpoint_per_partition() {
+        let endpoint = |region, fips, endpoint_override| {
+            resolve_sts_endpoint(region, fips, endpoint_override).map(|e| (e.url, e.signing_region))
+        };
+        assert_eq!(
+            endpoint("cn-north-1", false, None).unwrap().0,
+            "https://sts.cn-north-1.amazonaws.com.cn"
+        );
+        assert_eq!(
+            endpoint("us-east-2", true, None).unwrap().0,
+            "https://sts-fips.us-east-2.amazonaws.com"
+        );
+        assert_eq!(
+            endpoint("us-gov-west-1", true, None).unwrap().0,
+            "https://sts.us-gov-west-1.amazonaws.com"
+        );
+        assert!(endpoint("cn-north-1", true, None).is_err());
+        assert_eq!(
+            endpoint("eu-west-1", false, Some("https://sts.amazonaws.com/")).unwrap(),
+            (
+                "https://sts.amazonaws.com".to_string(),
+                "us-east-1".to_string()
+            )
+        );
+        assert_eq!(
+            endpoint(
+                "eu-west-1",
+                false,
+                Some("https://vpce-1.sts.eu-west-1.vpce.amazonaws.com")
+            )
+            .unwrap()
+            .1,
+            "eu-west-1"
+        );
+    }
+}
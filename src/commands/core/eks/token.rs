@@ -0,0 +1,264 @@
+use crate::cmd::OutputFormat;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, NaiveDateTime, TimeDelta, Utc};
+use reqwest::Url;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+use super::sign::{sts_signing_region, K8S_AWS_ID_HEADER, TOKEN_PREFIX};
+
+// aws-iam-authenticator rejects tokens signed more than 15 minutes ago, whatever X-Amz-Expires says
+const MAX_TOKEN_AGE: TimeDelta = TimeDelta::minutes(15);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error reading token from stdin: {0}")]
+    Stdin(#[from] std::io::Error),
+    #[error("Token does not start with {TOKEN_PREFIX}.")]
+    MissingPrefix,
+    #[error("Token is not valid base64url: {0}")]
+    Decode(#[from] base64::DecodeError),
+    #[error("Token does not contain a valid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Error formatting token inspection: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub type Result<T = ()> = std::result::Result<T, Error>;
+
+#[derive(Debug, Serialize)]
+pub struct TokenInspection {
+    pub url: String,
+    pub host: String,
+    pub action: Option<String>,
+    pub version: Option<String>,
+    pub access_key_id: Option<String>,
+    pub credential_scope: Option<String>,
+    pub region: Option<String>,
+    pub service: Option<String>,
+    pub signed_at: Option<DateTime<Utc>>,
+    pub expires_in_seconds: Option<i64>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub remaining_seconds: Option<i64>,
+    pub signed_headers: Vec<String>,
+    pub has_session_token: bool,
+    pub warnings: Vec<String>,
+}
+
+/// Decodes the token, accepting a whole ExecCredential as printed by `aws-auth eks` too.
+pub fn inspect_token(input: &str, now: DateTime<Utc>) -> Result<TokenInspection> {
+    let input = input.trim();
+    let token = if input.starts_with('{') {
+        let exec_credential: serde_json::Value = serde_json::from_str(input)?;
+        exec_credential["status"]["token"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_default()
+    } else {
+        input.to_string()
+    };
+    let encoded = token
+        .strip_prefix(TOKEN_PREFIX)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .ok_or(Error::MissingPrefix)?;
+    let decoded = URL_SAFE_NO_PAD.decode(encoded.trim_end_matches('='))?;
+    let url = String::from_utf8(decoded).map_err(|err| Error::InvalidUrl(err.to_string()))?;
+    let parsed = Url::parse(&url).map_err(|err| Error::InvalidUrl(err.to_string()))?;
+
+    let params: HashMap<String, String> = parsed
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    let host = parsed.host_str().unwrap_or_default().to_string();
+
+    // AKID/date/region/service/aws4_request
+    let credential = params.get("X-Amz-Credential");
+    let credential_parts: Vec<&str> = credential.map_or(Vec::new(), |c| c.split('/').collect());
+    let scope_part =
+        |index: usize| (credential_parts.len() == 5).then(|| credential_parts[index].to_string());
+    let region = scope_part(2);
+    let service = scope_part(3);
+
+    let signed_at = params
+        .get("X-Amz-Date")
+        .and_then(|date| NaiveDateTime::parse_from_str(date, "%Y%m%dT%H%M%SZ").ok())
+        .map(|date| date.and_utc());
+    let expires_in_seconds = params
+        .get("X-Amz-Expires")
+        .and_then(|expires| expires.parse::<i64>().ok());
+    let expires_at = signed_at
+        .zip(expires_in_seconds)
+        .map(|(signed_at, expires)| {
+            (signed_at + TimeDelta::seconds(expires)).min(signed_at + MAX_TOKEN_AGE)
+        });
+    let signed_headers: Vec<String> = params
+        .get("X-Amz-SignedHeaders")
+        .map_or(Vec::new(), |headers| {
+            headers.split(';').map(str::to_string).collect()
+        });
+
+    let mut warnings = Vec::new();
+    if parsed.scheme() != "https" {
+        warnings.push(format!(
+            "URL scheme is {}, STS requires https",
+            parsed.scheme()
+        ));
+    }
+    if params.get("Action").map(String::as_str) != Some("GetCallerIdentity") {
+        warnings.push("Action is not GetCallerIdentity".to_string());
+    }
+    if !signed_headers.iter().any(|h| h == K8S_AWS_ID_HEADER) {
+        warnings.push(format!(
+            "{K8S_AWS_ID_HEADER} is not a signed header, the token is not bound to a cluster"
+        ));
+    }
+    if service.as_deref() != Some("sts") {
+        warnings.push(format!(
+            "Credential scope service is {}, expected sts",
+            service.as_deref().unwrap_or("missing")
+        ));
+    }
+    match (&region, sts_signing_region(&host)) {
+        (Some(region), Some(expected)) if *region != expected => {
+            let kind = if host.contains(&format!(".{expected}.")) {
+                "Regional"
+            } else {
+                "Global"
+            };
+            warnings.push(format!(
+                "{kind} STS endpoint {host} is signed for {region}, it only verifies signatures for {expected}"
+            ))
+        }
+        _ => {}
+    }
+    if expires_in_seconds.is_some_and(|expires| expires > MAX_TOKEN_AGE.num_seconds()) {
+        warnings.push(format!(
+            "X-Amz-Expires is above {} seconds, EKS rejects the token {} minutes after signing",
+            MAX_TOKEN_AGE.num_seconds(),
+            MAX_TOKEN_AGE.num_minutes()
+        ));
+    }
+    let remaining_seconds = expires_at.map(|expires_at| (expires_at - now).num_seconds());
+    if remaining_seconds.is_some_and(|remaining| remaining <= 0) {
+        warnings.push("Token has expired".to_string());
+    }
+    if signed_at.is_some_and(|signed_at| signed_at > now + TimeDelta::minutes(5)) {
+        warnings.push("Token is signed in the future, check the local clock".to_string());
+    }
+
+    Ok(TokenInspection {
+        url,
+        host,
+        action: params.get("Action").cloned(),
+        version: params.get("Version").cloned(),
+        access_key_id: scope_part(0),
+        credential_scope: credential_parts
+            .get(1..)
+            .filter(|_| credential_parts.len() == 5)
+            .map(|scope| scope.join("/")),
+        region,
+        service,
+        signed_at,
+        expires_in_seconds,
+        expires_at,
+        remaining_seconds,
+        signed_headers,
+        has_session_token: params.contains_key("X-Amz-Security-Token"),
+        warnings,
+    })
+}
+
+fn print_text(inspection: &TokenInspection) {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+    let rfc3339 = |value: &Option<DateTime<Utc>>| {
+        value.map_or("-".to_string(), |dt| {
+            dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        })
+    };
+    let fields = [
+        ("URL", inspection.url.clone()),
+        ("Host", inspection.host.clone()),
+        ("Action", optional(&inspection.action)),
+        ("Version", optional(&inspection.version)),
+        ("Access key id", optional(&inspection.access_key_id)),
+        ("Credential scope", optional(&inspection.credential_scope)),
+        ("Region", optional(&inspection.region)),
+        ("Signed at", rfc3339(&inspection.signed_at)),
+        (
+            "X-Amz-Expires",
+            inspection
+                .expires_in_seconds
+                .map_or("-".to_string(), |v| format!("{v}s")),
+        ),
+        ("Expires at", rfc3339(&inspection.expires_at)),
+        (
+            "Remaining",
+            inspection
+                .remaining_seconds
+                .map_or("-".to_string(), |v| format!("{}s", v.max(0))),
+        ),
+        ("Signed headers", inspection.signed_headers.join(";")),
+        (
+            "Session token",
+            if inspection.has_session_token {
+                "present"
+            } else {
+                "absent"
+            }
+            .to_string(),
+        ),
+    ];
+    for (field, value) in fields {
+        println!("{:<17} {value}", format!("{field}:"));
+    }
+    for warning in &inspection.warnings {
+        println!("WARNING: {warning}");
+    }
+}
+
+/// Decodes and prints the token, read from stdin when not given or "-".
+pub fn exec_inspect(token: Option<&str>, output: &OutputFormat) -> Result {
+    let token = match token {
+        Some(token) if token != "-" => token.to_string(),
+        _ => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+    let inspection = inspect_token(&token, Utc::now())?;
+    match output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&inspection)?),
+        OutputFormat::Text => print_text(&inspection),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(url: &str) -> String {
+        format!("{TOKEN_PREFIX}.{}", URL_SAFE_NO_PAD.encode(url))
+    }
+
+    #[test]
+    fn test_inspect_token_flags_global_endpoint_mismatch() {
+        let token = encode("https://sts.amazonaws.com/?Action=GetCallerIdentity&Version=2011-06-15&X-Amz-Algorithm=AWS4-HMAC-SHA256&X-Amz-Credential=AKID%2F20300101%2Feu-west-1%2Fsts%2Faws4_request&X-Amz-Date=20300101T000000Z&X-Amz-Expires=860&X-Amz-SignedHeaders=host%3Bx-k8s-aws-id&X-Amz-Signature=abc");
+        let now = "2030-01-01T00:10:00Z".parse::<DateTime<Utc>>().unwrap();
+        let inspection = inspect_token(&token, now).unwrap();
+
+        assert_eq!(inspection.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(
+            inspection.credential_scope.as_deref(),
+            Some("20300101/eu-west-1/sts/aws4_request")
+        );
+        assert_eq!(inspection.remaining_seconds, Some(260));
+        assert_eq!(inspection.warnings.len(), 1);
+        assert!(inspection.warnings[0].starts_with("Global STS endpoint"));
+
+        let regional = encode("https://sts.eu-west-1.amazonaws.com/?Action=GetCallerIdentity&X-Amz-Credential=AKID%2F20300101%2Feu-west-1%2Fsts%2Faws4_request&X-Amz-SignedHeaders=host%3Bx-k8s-aws-id");
+        assert!(inspect_token(&regional, now).unwrap().warnings.is_empty());
+    }
+}
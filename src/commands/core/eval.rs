@@ -4,14 +4,94 @@ use chrono::{DateTime, Utc};
 
 use aws_sdk_ssooidc::config::Credentials;
 
-use crate::cmd::EvalOutputFormat;
+use crate::cmd::{EvalOutputFormat, Shell};
+
+/// Environment variables set by the eval output, cleared by --unset
+pub const EVAL_ENV_VARS: [&str; 6] = [
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+    "AWS_REGION",
+    "AWS_DEFAULT_REGION",
+    "AWS_SSO_SESSION_EXPIRATION",
+];
 
 pub struct ExecEvalInputs<'a> {
     pub region: Region,
     pub output: &'a EvalOutputFormat,
+    pub shell: Option<Shell>,
+}
+
+/// Detects the shell from the $SHELL basename, falling back to the platform default.
+pub fn detect_shell() -> Shell {
+    let from_env = std::env::var("SHELL").ok().and_then(|shell| {
+        let name = std::path::Path::new(&shell)
+            .file_stem()?
+            .to_string_lossy()
+            .to_lowercase();
+        match name.as_str() {
+            "bash" | "sh" | "dash" | "ksh" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "nu" => Some(Shell::Nu),
+            "pwsh" | "powershell" => Some(Shell::Powershell),
+            _ => None,
+        }
+    });
+    match from_env {
+        Some(shell) => shell,
+        None if cfg!(windows) && std::env::var_os("PSModulePath").is_some() => Shell::Powershell,
+        None if cfg!(windows) => Shell::Cmd,
+        None => Shell::Bash,
+    }
+}
+
+/// Formats a statement setting the variable in the given shell, quoting the value so it is taken literally.
+pub fn format_set(shell: Shell, key: &str, value: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("export {key}='{}'", value.replace('\'', r"'\''")),
+        Shell::Fish => format!(
+            "set -gx {key} '{}'",
+            value.replace('\\', r"\\").replace('\'', r"\'")
+        ),
+        Shell::Powershell => format!("$env:{key} = '{}'", value.replace('\'', "''")),
+        Shell::Nu => format!(
+            "$env.{key} = \"{}\"",
+            value.replace('\\', r"\\").replace('"', "\\\"")
+        ),
+        Shell::Cmd => format!("set \"{key}={value}\""),
+        Shell::Dotenv => format!(
+            "{key}=\"{}\"",
+            value.replace('\\', r"\\").replace('"', "\\\"")
+        ),
+        Shell::GithubActions => format!("{key}={value}"),
+    }
+}
+
+/// Formats a statement removing the variable in the given shell.
+pub fn format_unset(shell: Shell, key: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("unset {key}"),
+        Shell::Fish => format!("set -e {key}"),
+        Shell::Powershell => format!("Remove-Item Env:{key} -ErrorAction SilentlyContinue"),
+        Shell::Nu => format!("hide-env -i {key}"),
+        Shell::Cmd => format!("set {key}="),
+        Shell::Dotenv | Shell::GithubActions => format!("{key}="),
+    }
+}
+
+pub fn exec_unset(shell: Option<Shell>) {
+    let shell = shell.unwrap_or_else(detect_shell);
+    for key in EVAL_ENV_VARS {
+        println!("{}", format_unset(shell, key));
+    }
 }
 
 pub fn exec_eval(credentials: Credentials, exec_inputs: ExecEvalInputs) {
+    let expiration = credentials.expiry().map(|e| {
+        let dt: DateTime<Utc> = e.into();
+        dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    });
     match exec_inputs.output {
         EvalOutputFormat::Json => {
             let output = serde_json::json!({
@@ -19,42 +99,44 @@ pub fn exec_eval(credentials: Credentials, exec_inputs: ExecEvalInputs) {
                 "secret_access_key": credentials.secret_access_key(),
                 "region": exec_inputs.region.to_string(),
                 "session_token": credentials.session_token(),
-                "expiration": credentials.expiry().map(|e| {
-                    let dt: DateTime<Utc> = e.into();
-                    dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
-                })
+                "expiration": expiration,
             });
             println!("{}", output)
         }
         EvalOutputFormat::Eval => {
-            #[cfg(windows)]
-            let (prefix, quote) = ("$env:", '"');
-            #[cfg(not(windows))]
-            let (prefix, quote) = ("export ", '\'');
-
-            println!(
-                "{prefix}AWS_ACCESS_KEY_ID={quote}{}{quote}",
-                credentials.access_key_id()
-            );
-            println!(
-                "{prefix}AWS_SECRET_ACCESS_KEY={quote}{}{quote}",
-                credentials.secret_access_key()
-            );
-            if let Some(token) = credentials.session_token() {
-                println!("{prefix}AWS_SESSION_TOKEN={quote}{token}{quote}");
-            }
-            println!("{prefix}AWS_REGION={quote}{}{quote}", exec_inputs.region);
-            println!(
-                "{prefix}AWS_DEFAULT_REGION={quote}{}{quote}",
-                exec_inputs.region
-            );
-            if let Some(expiry) = credentials.expiry() {
-                let dt: DateTime<Utc> = expiry.into();
-                println!(
-                    "{prefix}AWS_SSO_SESSION_EXPIRATION={quote}{}{quote}",
-                    dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
-                );
+            let shell = exec_inputs.shell.unwrap_or_else(detect_shell);
+            let region = exec_inputs.region.to_string();
+            let values = [
+                Some(credentials.access_key_id()),
+                Some(credentials.secret_access_key()),
+                credentials.session_token(),
+                Some(region.as_str()),
+                Some(region.as_str()),
+                expiration.as_deref(),
+            ];
+            for (key, value) in EVAL_ENV_VARS.into_iter().zip(values) {
+                if let Some(value) = value {
+                    println!("{}", format_set(shell, key, value));
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_set_quotes_single_quotes() {
+        let value = "it's";
+        assert_eq!(format_set(Shell::Bash, "K", value), r"export K='it'\''s'");
+        assert_eq!(format_set(Shell::Fish, "K", value), r"set -gx K 'it\'s'");
+        assert_eq!(
+            format_set(Shell::Powershell, "K", value),
+            "$env:K = 'it''s'"
+        );
+        assert_eq!(format_set(Shell::Nu, "K", "a\"b"), "$env.K = \"a\\\"b\"");
+        assert_eq!(format_set(Shell::Dotenv, "K", "a\\b"), "K=\"a\\\\b\"");
+    }
+}
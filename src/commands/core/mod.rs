@@ -1,22 +1,32 @@
-mod eks;
+mod console;
+mod ecr;
+pub mod eks;
 mod eval;
 mod exec;
+mod profile;
 
 use aws_config::Region;
-use chrono::Duration;
-use eks::ExecEksInputs;
+use console::ExecConsoleInputs;
+use ecr::{ExecCredentialHelperInputs, ExecEcrLoginInputs};
+use eks::{kubeconfig::ExecKubeconfigInputs, prefetch::ExecPrefetchInputs, ExecEksInputs};
 use eval::ExecEvalInputs;
 use exec::ExecExecInputs;
+use profile::ExecWriteProfileInputs;
 
 use crate::{
     alias_providers,
-    aws_sso::{build_sso_mgr_cached, AwsSsoManagerError},
-    cmd::CoreCommands,
-    utils::{resolve_assume_identifier, resolve_config_dir},
+    aws_sso::{build_sso_mgr_cached, config::AwsSsoConfig, AwsSsoConfigError, AwsSsoManagerError},
+    cmd::{CoreCommands, EcrCommands, EksCommands, EksTokenCommands},
+    utils::{
+        credentials_file::default_credentials_file, region::resolve_region, render_placeholders,
+        resolve_assume_identifier, resolve_config_dir,
+    },
 };
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
     #[error("Error resolving assume identifier: {0}")]
     AssumeIdResolver(String),
     #[error("Error resolving SSO credentials: {0}")]
@@ -25,6 +35,24 @@ pub enum Error {
     CmdExec(#[from] exec::Error),
     #[error("Error executing EKS command: {0}")]
     CmdEks(#[from] eks::Error),
+    #[error("Error writing kubeconfig: {0}")]
+    CmdEksKubeconfig(#[from] eks::kubeconfig::Error),
+    #[error("Error prefetching EKS tokens: {0}")]
+    CmdEksPrefetch(#[from] eks::prefetch::Error),
+    #[error("Error inspecting token: {0}")]
+    CmdEksToken(#[from] eks::token::Error),
+    #[error("Error logging in to ECR: {0}")]
+    CmdEcr(#[from] ecr::Error),
+    #[error("Provide --cluster or configure eks_cluster")]
+    MissingCluster,
+    #[error("Error resolving region: {0}")]
+    Region(String),
+    #[error("Provide --profile when not using an alias")]
+    MissingProfile,
+    #[error("Error writing profile: {0}")]
+    CmdWriteProfile(#[from] profile::Error),
+    #[error("Error opening console: {0}")]
+    CmdConsole(#[from] console::Error),
 }
 
 impl From<AwsSsoManagerError> for Error {
@@ -33,13 +61,119 @@ impl From<AwsSsoManagerError> for Error {
     }
 }
 
+/// Executes the commands that do not assume a single role from the common arguments.
+async fn exec_standalone_commands(command: &CoreCommands) -> Result<(), Error> {
+    match command {
+        CoreCommands::Eks {
+            command: Some(eks_command),
+            ..
+        } => match eks_command.as_ref() {
+            EksCommands::Token {
+                command: EksTokenCommands::Inspect { token, output },
+            } => eks::token::exec_inspect(token.as_deref(), output)?,
+            EksCommands::Prefetch {
+                targets,
+                from_kubeconfig,
+                kubeconfig,
+                config_dir,
+                sso_cache_dir,
+                team_aliases,
+                ignore_cache,
+                refresh_sts_token,
+                region,
+                parallel,
+                token,
+            } => {
+                let kubeconfig = from_kubeconfig.then(|| {
+                    kubeconfig
+                        .clone()
+                        .unwrap_or_else(eks::kubeconfig::default_kubeconfig)
+                });
+                eks::prefetch::exec_prefetch(ExecPrefetchInputs {
+                    targets,
+                    kubeconfig: kubeconfig.as_deref(),
+                    config_dir: &resolve_config_dir(config_dir.as_deref()),
+                    sso_cache_dir: sso_cache_dir.as_deref(),
+                    team_aliases: team_aliases.as_deref(),
+                    ignore_cache: *ignore_cache,
+                    refresh_sts_token: *refresh_sts_token,
+                    region: region.as_deref(),
+                    parallel: *parallel,
+                    token,
+                })
+                .await?
+            }
+            EksCommands::Kubeconfig { .. } => {}
+        },
+        CoreCommands::Ecr {
+            command:
+                EcrCommands::CredentialHelper {
+                    action,
+                    config_dir,
+                    sso_cache_dir,
+                    team_aliases,
+                    ecr_endpoint,
+                },
+        } => {
+            ecr::exec_credential_helper(ExecCredentialHelperInputs {
+                action: *action,
+                config_dir: &resolve_config_dir(config_dir.as_deref()),
+                sso_cache_dir: sso_cache_dir.as_deref(),
+                team_aliases: team_aliases.as_deref(),
+                ecr_endpoint: ecr_endpoint.as_deref(),
+            })
+            .await?
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 pub async fn exec_core_commands(command: &CoreCommands) -> Result<(), Error> {
-    let common_args = command.get_common_args();
+    let Some(common_args) = command.get_common_args() else {
+        return exec_standalone_commands(command).await;
+    };
+    if let CoreCommands::Eval {
+        shell, unset: true, ..
+    } = command
+    {
+        eval::exec_unset(*shell);
+        return Ok(());
+    }
+
     let config_dir = resolve_config_dir(common_args.config_dir.as_deref());
-    let mut sso_manager = build_sso_mgr_cached(&config_dir, common_args.sso_cache_dir.as_deref());
-    let mut alias_provider = alias_providers::build_alias_provider(&config_dir);
-    let assume_identity = resolve_assume_identifier(&mut alias_provider, common_args)
-        .map_err(|err| Error::AssumeIdResolver(err.to_string()))?;
+    let (settings, sources) = AwsSsoConfig::load_effective_config_with_sources(&config_dir)?;
+    let (pinned_region, default_region) = settings.configured_regions(&sources);
+    let mut sso_manager = build_sso_mgr_cached(&config_dir, common_args.sso_cache_dir.as_deref())?;
+    let mut alias_provider =
+        alias_providers::build_alias_provider(&config_dir, common_args.team_aliases.as_deref())
+            .map_err(|err| Error::AssumeIdResolver(err.to_string()))?;
+    let assume_identity = resolve_assume_identifier(
+        &mut alias_provider,
+        common_args,
+        settings.default_alias.as_deref(),
+    )
+    .map_err(|err| Error::AssumeIdResolver(err.to_string()))?;
+    let region = Region::new(
+        resolve_region(
+            common_args.region.as_deref(),
+            assume_identity.region,
+            pinned_region,
+            default_region,
+            &settings.sso_region,
+        )
+        .map_err(Error::Region)?,
+    );
+
+    // Alias the identity was resolved from, None when account and role were given
+    let alias = match &common_args.assume_input.account {
+        Some(_) => None,
+        None => common_args
+            .assume_input
+            .alias
+            .as_deref()
+            .or(settings.default_alias.as_deref()),
+    };
 
     let mut credential_resolver = async || {
         sso_manager
@@ -54,32 +188,151 @@ pub async fn exec_core_commands(command: &CoreCommands) -> Result<(), Error> {
 
     match command {
         CoreCommands::Eks {
-            cluster,
-            eks_cache_dir,
-            eks_expiry_seconds,
+            command: Some(eks_command),
             ..
-        } => {
+        } => match eks_command.as_ref() {
+            EksCommands::Kubeconfig {
+                cluster,
+                kubeconfig,
+                context_name,
+                server,
+                certificate_authority_data,
+                eks_endpoint,
+                exec_command,
+                api_version,
+                set_current_context,
+                ..
+            } => {
+                let cluster = cluster
+                    .as_deref()
+                    .or(settings.eks_cluster.as_deref())
+                    .ok_or(Error::MissingCluster)?;
+                let context_name = render_placeholders(
+                    context_name,
+                    &[
+                        ("alias", alias.unwrap_or(assume_identity.account)),
+                        ("account", assume_identity.account),
+                        ("role", assume_identity.role),
+                        ("region", region.as_ref()),
+                        ("cluster", cluster),
+                    ],
+                );
+
+                let mut exec_args = vec!["eks".to_string()];
+                match alias {
+                    Some(alias) => exec_args.extend(["-A".to_string(), alias.to_string()]),
+                    None => exec_args.extend([
+                        "-a".to_string(),
+                        assume_identity.account.to_string(),
+                        "-r".to_string(),
+                        assume_identity.role.to_string(),
+                    ]),
+                }
+                exec_args.extend([
+                    "-c".to_string(),
+                    cluster.to_string(),
+                    "-R".to_string(),
+                    region.to_string(),
+                ]);
+                for (flag, path) in [
+                    ("--config-dir", &common_args.config_dir),
+                    ("--sso-cache-dir", &common_args.sso_cache_dir),
+                    ("--team-aliases", &common_args.team_aliases),
+                ] {
+                    if let Some(path) = path {
+                        exec_args.extend([flag.to_string(), path.display().to_string()]);
+                    }
+                }
+
+                let kubeconfig = kubeconfig
+                    .clone()
+                    .unwrap_or_else(eks::kubeconfig::default_kubeconfig);
+                eks::kubeconfig::exec_kubeconfig(
+                    credential_resolver,
+                    ExecKubeconfigInputs {
+                        cluster,
+                        region: region.clone(),
+                        kubeconfig: &kubeconfig,
+                        context_name: &context_name,
+                        server: server.as_deref(),
+                        certificate_authority_data: certificate_authority_data.as_deref(),
+                        eks_endpoint: eks_endpoint.as_deref(),
+                        exec_command,
+                        api_version,
+                        exec_args,
+                        set_current_context: *set_current_context,
+                    },
+                )
+                .await?;
+            }
+            EksCommands::Prefetch { .. } | EksCommands::Token { .. } => {
+                unreachable!("handled by exec_standalone_commands")
+            }
+        },
+        CoreCommands::Eks { cluster, token, .. } => {
+            let cluster = cluster
+                .as_deref()
+                .or(settings.eks_cluster.as_deref())
+                .ok_or(Error::MissingCluster)?;
             eks::exec_eks(
-                credential_resolver,
-                ExecEksInputs {
-                    account: assume_identity.account,
-                    role: assume_identity.role,
-                    cluster,
-                    region: Region::new(common_args.region.clone()),
-                    eks_cache_dir: eks_cache_dir.as_deref(),
-                    config_dir: &config_dir,
-                    expiry: eks_expiry_seconds.map(|v| Duration::seconds(v as i64)),
+                async |refresh| {
+                    sso_manager
+                        .assume_role(
+                            assume_identity.account,
+                            assume_identity.role,
+                            refresh || common_args.refresh_sts_token,
+                            common_args.ignore_cache && !refresh,
+                        )
+                        .await
                 },
+                ExecEksInputs::new(
+                    assume_identity.account,
+                    assume_identity.role,
+                    cluster,
+                    region.clone(),
+                    &config_dir,
+                    token,
+                ),
             )
             .await?;
         }
-        CoreCommands::Eval { output, .. } => {
+        CoreCommands::Ecr { command } => match command {
+            EcrCommands::Login {
+                registry_account,
+                docker_config,
+                ecr_endpoint,
+                print,
+                ..
+            } => {
+                let docker_config = docker_config.clone().unwrap_or_else(|| {
+                    home::home_dir()
+                        .unwrap_or_else(std::env::temp_dir)
+                        .join(".docker")
+                });
+                ecr::exec_login(
+                    credential_resolver,
+                    ExecEcrLoginInputs {
+                        region: region.clone(),
+                        registry_accounts: registry_account,
+                        docker_config: &docker_config,
+                        ecr_endpoint: ecr_endpoint.as_deref(),
+                        print: *print,
+                    },
+                )
+                .await?;
+            }
+            EcrCommands::CredentialHelper { .. } => {
+                unreachable!("handled by exec_standalone_commands")
+            }
+        },
+        CoreCommands::Eval { output, shell, .. } => {
             let credentials = credential_resolver().await?;
             eval::exec_eval(
                 credentials,
                 ExecEvalInputs {
-                    region: Region::new(common_args.region.clone()),
+                    region: region.clone(),
                     output,
+                    shell: *shell,
                 },
             );
         }
@@ -88,12 +341,65 @@ pub async fn exec_core_commands(command: &CoreCommands) -> Result<(), Error> {
             exec::exec_exec(
                 credentials,
                 ExecExecInputs {
-                    region: Region::new(common_args.region.clone()),
+                    region: region.clone(),
                     arguments: arguments.clone(),
                 },
             )
             .await?;
         }
+        CoreCommands::Console {
+            destination,
+            service,
+            session_duration_seconds,
+            federation_endpoint,
+            print,
+            ..
+        } => {
+            let credentials = credential_resolver().await?;
+            console::exec_console(
+                credentials,
+                ExecConsoleInputs {
+                    region: region.clone(),
+                    destination: destination.as_deref(),
+                    service: service.as_deref(),
+                    session_duration: *session_duration_seconds,
+                    federation_endpoint: federation_endpoint.as_deref(),
+                    print: *print,
+                },
+            )
+            .await?;
+        }
+        CoreCommands::WriteProfile {
+            profile,
+            credentials_file,
+            watch,
+            refresh_before_seconds,
+            ..
+        } => {
+            let profile = profile.as_deref().or(alias).ok_or(Error::MissingProfile)?;
+            let credentials_file = credentials_file
+                .clone()
+                .unwrap_or_else(default_credentials_file);
+            profile::exec_write_profile(
+                async |refresh| {
+                    sso_manager
+                        .assume_role(
+                            assume_identity.account,
+                            assume_identity.role,
+                            refresh || common_args.refresh_sts_token,
+                            common_args.ignore_cache && !refresh,
+                        )
+                        .await
+                },
+                ExecWriteProfileInputs {
+                    profile,
+                    credentials_file: &credentials_file,
+                    watch: *watch,
+                    refresh_before: std::time::Duration::from_secs(*refresh_before_seconds),
+                },
+            )
+            .await?;
+        }
     }
     Ok(())
 }
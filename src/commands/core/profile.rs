@@ -0,0 +1,82 @@
+use crate::aws_sso::AwsSsoManagerError;
+use crate::utils::credentials_file::write_credentials_profiles;
+use aws_sdk_ssooidc::config::Credentials;
+use chrono::{DateTime, Utc};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+// Lower bound between rewrites in watch mode, protects against credentials that expire too soon
+const MIN_WATCH_INTERVAL: Duration = Duration::from_secs(30);
+// Rewrite interval used when the credentials have no expiry
+const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+pub struct ExecWriteProfileInputs<'a> {
+    pub profile: &'a str,
+    pub credentials_file: &'a Path,
+    pub watch: bool,
+    pub refresh_before: Duration,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error resolving SSO credentials: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("Error writing credentials file {0:?}: {1}")]
+    Write(std::path::PathBuf, io::Error),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub type Result = std::result::Result<(), Error>;
+
+/// Writes the credentials to the profile, in watch mode the profile is rewritten with
+/// refreshed credentials `refresh_before` ahead of every expiry.
+/// The resolver receives true when the credentials must be refreshed.
+pub async fn exec_write_profile<F>(
+    mut credential_resolver: F,
+    exec_inputs: ExecWriteProfileInputs<'_>,
+) -> Result
+where
+    F: AsyncFnMut(bool) -> std::result::Result<Credentials, AwsSsoManagerError>,
+{
+    let mut refresh = false;
+    loop {
+        let credentials = credential_resolver(refresh).await?;
+        write_credentials_profiles(
+            exec_inputs.credentials_file,
+            &[(exec_inputs.profile, &credentials)],
+        )
+        .map_err(|err| Error::Write(exec_inputs.credentials_file.to_path_buf(), err))?;
+
+        let expiry = credentials.expiry();
+        println!(
+            "INFO: Wrote credentials for profile {} to {}{}",
+            exec_inputs.profile,
+            exec_inputs.credentials_file.display(),
+            expiry.map_or(String::new(), |expiry| {
+                let dt: DateTime<Utc> = expiry.into();
+                format!(
+                    ", expiring at {}",
+                    dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                )
+            })
+        );
+        if !exec_inputs.watch {
+            return Ok(());
+        }
+
+        let wait = expiry.map_or(DEFAULT_WATCH_INTERVAL, |expiry| {
+            expiry
+                .duration_since(SystemTime::now())
+                .unwrap_or_default()
+                .saturating_sub(exec_inputs.refresh_before)
+        });
+        tokio::time::sleep(wait.max(MIN_WATCH_INTERVAL)).await;
+        refresh = true;
+    }
+}
@@ -1,15 +1,43 @@
+use crate::alias_providers::{build_alias_provider_and_load, AliasProviderError, ProvideAliases};
 use crate::aws_sso::config::AwsSsoConfig;
-use crate::utils::resolve_config_dir;
-use std::fs::File;
-use std::path::PathBuf;
+use crate::aws_sso::{build_sso_mgr_cached, AwsSsoConfigError, AwsSsoManagerError};
+use crate::common::AssumeIdentifier;
+use crate::utils::{prompt, region, resolve_config_dir};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
 
 // Directories relative to the config directory
 const RELATIVE_DIRS: [&str; 1] = ["eks"];
 
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("Error saving config: {0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error verifying the configuration by logging in: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+    #[error("Error importing aliases: {0}")]
+    AliasProvider(AliasProviderError),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
 pub struct ExecInitInputs {
     pub config_dir: Option<PathBuf>,
     pub update: bool,
     pub recreate: bool,
+    pub non_interactive: bool,
+    pub login: bool,
+    pub import_aliases: bool,
 
     pub sso_start_url: Option<String>,
     pub sso_region: Option<String>,
@@ -17,32 +45,285 @@ pub struct ExecInitInputs {
     pub initial_delay: Option<std::time::Duration>,
     pub retry_interval: Option<std::time::Duration>,
     pub create_token_retry_threshold: Option<u64>,
-    pub create_token_lock_decay: Option<chrono::Duration>,
+    pub create_token_lock_decay: Option<std::time::Duration>,
+}
+
+/// Whether the lowercase host and the path, without its leading slash, are those of an
+/// IAM Identity Center portal: `<name>.awsapps.com/start`, `<name>.awsapps.cn/start`
+/// or the regional `ssoins-<id>.portal.<region>.app.aws`.
+pub fn is_start_url(host: &str, path: &str) -> bool {
+    host.ends_with(".app.aws")
+        || ((host.ends_with(".awsapps.com") || host.ends_with(".awsapps.cn"))
+            && path.starts_with("start"))
+}
+
+/// Normalises the accepted start URL shapes into a full https URL:
+/// `my-company`, `d-1234567890`, `my-company.awsapps.com`, a copied portal URL such as
+/// `https://my-company.awsapps.com/start/#/` and the regional `https://ssoins-<id>.portal.<region>.app.aws`.
+pub fn normalize_start_url(input: &str) -> Result<String, String> {
+    let input = input.trim().trim_end_matches('/');
+    if input.is_empty() {
+        return Err("SSO start URL cannot be empty".to_string());
+    }
+    let name_regex =
+        Regex::new(r"^[A-Za-z0-9][A-Za-z0-9-]*$").expect("Static regex should be valid");
+    if name_regex.is_match(input) {
+        return Ok(format!("https://{input}.awsapps.com/start"));
+    }
+
+    let without_scheme = input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))
+        .unwrap_or(input);
+    let (host, path) = without_scheme
+        .split_once('/')
+        .map_or((without_scheme, ""), |(host, path)| (host, path));
+    let host = host.to_lowercase();
+    if host.is_empty() || !host.contains('.') || host.contains(char::is_whitespace) {
+        return Err(format!(
+            "{input:?} is not a valid start URL, expected a name such as my-company or a URL such as https://my-company.awsapps.com/start"
+        ));
+    }
+    if host.ends_with(".awsapps.com") || host.ends_with(".awsapps.cn") {
+        // Drop the portal fragments and sub pages copied from the browser
+        if path.is_empty() || path.starts_with("start") || path.starts_with('#') {
+            return Ok(format!("https://{host}/start"));
+        }
+    }
+    if host.ends_with(".app.aws") {
+        return Ok(format!("https://{host}"));
+    }
+    let path = path.split('#').next().unwrap_or("").trim_end_matches('/');
+    if path.is_empty() {
+        Ok(format!("https://{host}"))
+    } else {
+        Ok(format!("https://{host}/{path}"))
+    }
+}
+
+/// Guesses the SSO region from the start URL or the AWS environment variables.
+fn guess_sso_region(start_url: &str) -> Option<String> {
+    let portal_regex = Regex::new(r"^https://[^/]+\.portal\.([a-z0-9-]+)\.app\.aws")
+        .expect("Static regex should be valid");
+    if let Some(captures) = portal_regex.captures(start_url) {
+        return Some(captures[1].to_string());
+    }
+    if start_url.contains(".awsapps.cn/") {
+        return Some("cn-north-1".to_string());
+    }
+    ["AWS_REGION", "AWS_DEFAULT_REGION"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|region| region::find_partition(region).is_some())
+}
+
+fn resolve_start_url(start_url: Option<String>, interactive: bool) -> Result<String, Error> {
+    if let Some(start_url) = start_url {
+        return normalize_start_url(&start_url).map_err(Error::InvalidInput);
+    }
+    if !interactive {
+        return Err(Error::InvalidInput(
+            "--sso-start-url is required when not updating and not running interactively"
+                .to_string(),
+        ));
+    }
+    loop {
+        let answer = prompt::prompt(
+            "SSO start URL or organisation name (e.g. my-company or https://my-company.awsapps.com/start)",
+            None,
+        )?;
+        match normalize_start_url(&answer) {
+            Ok(start_url) => return Ok(start_url),
+            Err(err) => eprintln!("ERROR: {err}"),
+        }
+    }
+}
+
+fn resolve_sso_region(
+    sso_region: Option<String>,
+    start_url: &str,
+    interactive: bool,
+) -> Result<String, Error> {
+    if let Some(sso_region) = sso_region {
+        region::validate_region(&sso_region).map_err(Error::InvalidInput)?;
+        return Ok(sso_region);
+    }
+    let guess = guess_sso_region(start_url);
+    if !interactive {
+        return match guess {
+            Some(guess) => {
+                println!(
+                    "INFO: Using SSO region {guess} guessed from the start URL or environment"
+                );
+                Ok(guess)
+            }
+            None => Err(Error::InvalidInput(
+                "--sso-region is required when it cannot be guessed and not running interactively"
+                    .to_string(),
+            )),
+        };
+    }
+    loop {
+        let answer = prompt::prompt(
+            "AWS region hosting IAM Identity Center",
+            Some(guess.as_deref().unwrap_or("us-east-1")),
+        )?;
+        match region::validate_region(&answer) {
+            Ok(_) => return Ok(answer),
+            Err(err) => eprintln!("ERROR: {err}"),
+        }
+    }
+}
+
+fn alias_slug(value: &str) -> String {
+    let slug = value
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Suffixes aliases generated for several accounts with the account ID, e.g. for accounts named
+/// "Prod App" and "prod-app". Aliases still repeated, roles of one account with the same slug,
+/// are left out and returned.
+fn disambiguate_aliases(account_roles: &mut Vec<(String, String, String)>) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (alias, ..) in account_roles.iter() {
+        *counts.entry(alias.clone()).or_default() += 1;
+    }
+    for (alias, account, _) in account_roles.iter_mut() {
+        if counts[alias.as_str()] > 1 {
+            *alias = format!("{alias}-{account}");
+        }
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (alias, ..) in account_roles.iter() {
+        *counts.entry(alias.clone()).or_default() += 1;
+    }
+    let mut repeated = Vec::new();
+    let mut reported = HashSet::new();
+    account_roles.retain(|(alias, ..)| {
+        if counts[alias.as_str()] > 1 {
+            // Entries of one alias need not be adjacent, each alias is returned once
+            if reported.insert(alias.clone()) {
+                repeated.push(alias.clone());
+            }
+            false
+        } else {
+            true
+        }
+    });
+    repeated
 }
 
-#[derive(Debug, serde::Serialize)]
-struct InitConfig {
-    #[serde(flatten)]
-    sso_config: AwsSsoConfig,
+/// Logs in with the saved configuration and optionally creates an alias per account role.
+/// Aliases are named after the account, suffixed with the role when the account has several roles.
+async fn verify_and_import(config_dir: &Path, import_aliases: Option<bool>) -> Result<(), Error> {
+    let mut sso_manager = build_sso_mgr_cached(config_dir, None)?;
+    let accounts = sso_manager.list_accounts(false).await?;
+    println!(
+        "INFO: Login successful, {} account(s) are available",
+        accounts.len()
+    );
+
+    let import_aliases = match import_aliases {
+        Some(import_aliases) => import_aliases,
+        None => prompt::confirm("Create aliases for the available account roles?", false)?,
+    };
+    if !import_aliases {
+        return Ok(());
+    }
+
+    let mut account_roles = Vec::new();
+    for account in accounts.iter() {
+        let (Some(account_id), Some(account_name)) = (account.account_id(), account.account_name())
+        else {
+            continue;
+        };
+        let roles = sso_manager.list_account_roles(account_id, false).await?;
+        for role in roles.iter().filter_map(|role| role.role_name()) {
+            let alias = if roles.len() == 1 {
+                alias_slug(account_name)
+            } else {
+                format!("{}-{}", alias_slug(account_name), alias_slug(role))
+            };
+            account_roles.push((alias, account_id.to_string(), role.to_string()));
+        }
+    }
+
+    for alias in disambiguate_aliases(&mut account_roles) {
+        eprintln!("WARNING: Skipping alias {alias}, several roles of the account map to it");
+    }
+
+    let mut alias_provider =
+        build_alias_provider_and_load(config_dir, None).map_err(Error::AliasProvider)?;
+    let mut to_import = Vec::with_capacity(account_roles.len());
+    for (alias, account, role) in account_roles.iter() {
+        if alias_provider
+            .get_alias(alias)
+            .map_err(Error::AliasProvider)?
+            .is_none()
+        {
+            to_import.push((
+                alias.as_str(),
+                AssumeIdentifier {
+                    account,
+                    role,
+                    region: None,
+                },
+            ));
+        }
+    }
+    alias_provider
+        .set_aliases(&to_import)
+        .map_err(Error::AliasProvider)?;
+    println!(
+        "INFO: Created {} aliases, skipped {} existing aliases. Use `aws-auth alias list` to review them",
+        to_import.len(),
+        account_roles.len() - to_import.len()
+    );
+    Ok(())
 }
 
-pub fn exec_init(exec_inputs: ExecInitInputs) -> Result<(), std::io::Error> {
+pub async fn exec_init(exec_inputs: ExecInitInputs) -> Result<(), Error> {
     let config_dir = resolve_config_dir(exec_inputs.config_dir.as_deref());
     let config_dir_exists = config_dir.exists();
-    let config_file = config_dir.join("config.json");
+    let interactive = !exec_inputs.non_interactive && prompt::is_interactive();
 
-    if config_dir_exists && !(exec_inputs.recreate && exec_inputs.update) {
-        println!("INFO: Config dir exists at {config_dir:?}. No update flags are provided. Assuming dry-run and exiting with success");
+    if config_dir_exists && !(exec_inputs.recreate || exec_inputs.update) {
+        println!("INFO: Config dir exists at {config_dir:?}. No update flags are provided. Use --update, --recreate or `aws-auth config set` to change the configuration");
         return Ok(());
     }
 
     if exec_inputs.update && exec_inputs.recreate {
-        return Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "Cannot --update and --recreate at the same time.",
+        return Err(Error::InvalidInput(
+            "Cannot --update and --recreate at the same time.".to_string(),
         ));
     }
 
+    let updating = exec_inputs.update && config_dir_exists;
+    // Resolve the required values before touching the config directory so an invalid
+    // or aborted answer leaves the existing configuration untouched
+    let (sso_start_url, sso_region) = if updating {
+        let sso_start_url = exec_inputs
+            .sso_start_url
+            .map(|start_url| normalize_start_url(&start_url).map_err(Error::InvalidInput))
+            .transpose()?;
+        if let Some(sso_region) = &exec_inputs.sso_region {
+            region::validate_region(sso_region).map_err(Error::InvalidInput)?;
+        }
+        (sso_start_url, exec_inputs.sso_region)
+    } else {
+        let sso_start_url = resolve_start_url(exec_inputs.sso_start_url, interactive)?;
+        let sso_region = resolve_sso_region(exec_inputs.sso_region, &sso_start_url, interactive)?;
+        (Some(sso_start_url), Some(sso_region))
+    };
+
     if !config_dir_exists || exec_inputs.recreate {
         if config_dir_exists && exec_inputs.recreate {
             println!(
@@ -61,14 +342,13 @@ pub fn exec_init(exec_inputs: ExecInitInputs) -> Result<(), std::io::Error> {
         );
     }
 
-    let sso_config = if exec_inputs.update && config_dir_exists {
-        let mut sso_config = AwsSsoConfig::load_config(&config_file)
-            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
-        if let Some(start_url) = exec_inputs.sso_start_url {
+    let sso_config = if updating {
+        let mut sso_config = AwsSsoConfig::load_config(&config_dir)?;
+        if let Some(start_url) = sso_start_url {
             sso_config.start_url = start_url;
         }
-        if let Some(sso_region) = exec_inputs.sso_region {
-            sso_config.sso_reigon = sso_region;
+        if let Some(sso_region) = sso_region {
+            sso_config.sso_region = sso_region;
         }
         if let Some(max_attempts) = exec_inputs.max_attempts {
             sso_config.max_attempts = Some(max_attempts);
@@ -86,29 +366,111 @@ pub fn exec_init(exec_inputs: ExecInitInputs) -> Result<(), std::io::Error> {
             sso_config.create_token_lock_decay = Some(create_token_lock_decay);
         }
         sso_config
-    } else if exec_inputs.sso_start_url.is_some() || exec_inputs.sso_region.is_some() {
+    } else {
         AwsSsoConfig {
-            start_url: exec_inputs.sso_start_url.unwrap(),
-            sso_reigon: exec_inputs.sso_region.unwrap(),
             max_attempts: exec_inputs.max_attempts,
             initial_delay: exec_inputs.initial_delay,
             retry_interval: exec_inputs.retry_interval,
             create_token_retry_threshold: exec_inputs.create_token_retry_threshold,
             create_token_lock_decay: exec_inputs.create_token_lock_decay,
+            ..AwsSsoConfig::new(
+                sso_start_url.expect("Resolved above when not updating"),
+                sso_region.expect("Resolved above when not updating"),
+            )
         }
-    } else {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::InvalidInput,
-            "--sso-start-url and --sso-region are required when not updating.",
-        ))?
     };
 
-    let config_file = File::create(&config_file)?;
-    serde_json::to_writer_pretty(config_file, &InitConfig { sso_config })
-        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    sso_config.save_config(&config_dir)?;
     println!(
         "INFO: Successfully initialized/updated configuration in {}",
         config_dir.display()
     );
+
+    let login = exec_inputs.login
+        || exec_inputs.import_aliases
+        || (interactive && prompt::confirm("Log in now to verify the settings?", true)?);
+    if login {
+        let import_aliases =
+            (exec_inputs.import_aliases || !interactive).then_some(exec_inputs.import_aliases);
+        verify_and_import(&config_dir, import_aliases).await?;
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_start_url() {
+        let expected = "https://my-company.awsapps.com/start";
+        for input in [
+            "my-company",
+            "my-company.awsapps.com",
+            "https://my-company.awsapps.com",
+            "https://my-company.awsapps.com/start/#/",
+            "http://My-Company.awsapps.com/start#/?tab=accounts",
+        ] {
+            assert_eq!(normalize_start_url(input).unwrap(), expected, "{input}");
+        }
+        assert_eq!(
+            normalize_start_url("https://ssoins-1234.portal.eu-west-1.app.aws/").unwrap(),
+            "https://ssoins-1234.portal.eu-west-1.app.aws"
+        );
+        assert!(is_start_url("ssoins-1234.portal.eu-west-1.app.aws", ""));
+        assert!(!is_start_url("my-company.awsapps.com", "portal"));
+        assert_eq!(
+            guess_sso_region("https://ssoins-1234.portal.eu-west-1.app.aws").as_deref(),
+            Some("eu-west-1")
+        );
+        assert!(normalize_start_url("not a url").is_err());
+    }
+
+    #[test]
+    fn test_disambiguate_aliases_of_accounts_with_the_same_slug() {
+        let entry = |alias: &str, account: &str, role: &str| {
+            (alias.to_string(), account.to_string(), role.to_string())
+        };
+        let mut account_roles = vec![
+            entry("prod-app", "111111111111", "Admin"),
+            entry("prod-app", "222222222222", "Admin"),
+            entry("dev-read-only", "333333333333", "Read Only"),
+            entry("dev-read-only", "333333333333", "read-only"),
+            entry("sandbox", "444444444444", "Admin"),
+        ];
+        let repeated = disambiguate_aliases(&mut account_roles);
+        assert_eq!(repeated, ["dev-read-only-333333333333"]);
+        assert_eq!(
+            account_roles,
+            vec![
+                entry("prod-app-111111111111", "111111111111", "Admin"),
+                entry("prod-app-222222222222", "222222222222", "Admin"),
+                entry("sandbox", "444444444444", "Admin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disambiguate_aliases_reports_non_adjacent_repeats_once() {
+        let entry = |alias: &str, role: &str| {
+            (
+                alias.to_string(),
+                "333333333333".to_string(),
+                role.to_string(),
+            )
+        };
+        let mut account_roles = vec![
+            entry("dev-read-only", "Read Only"),
+            entry("dev-power-user", "Power User"),
+            entry("dev-admin", "Admin"),
+            entry("dev-read-only", "read-only"),
+            entry("dev-power-user", "power-user"),
+        ];
+        let repeated = disambiguate_aliases(&mut account_roles);
+        assert_eq!(
+            repeated,
+            ["dev-read-only-333333333333", "dev-power-user-333333333333"]
+        );
+        assert_eq!(account_roles, vec![entry("dev-admin", "Admin")]);
+    }
+}
@@ -1,13 +1,24 @@
-use crate::aws_sso::{build_sso_mgr_cached, AwsSsoManagerError};
+use crate::aws_sso::{build_sso_mgr_cached, AwsSsoConfigError, AwsSsoManagerError};
 use crate::utils::resolve_config_dir;
 use std::path::Path;
 
-pub async fn exec_logout(
-    config_dir: Option<&Path>,
-    cache_dir: Option<&Path>,
-) -> Result<(), AwsSsoManagerError> {
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error loading config: {0}")]
+    Config(#[from] AwsSsoConfigError),
+    #[error("Error logging out of AWS SSO: {0}")]
+    AwsSso(Box<AwsSsoManagerError>),
+}
+
+impl From<AwsSsoManagerError> for Error {
+    fn from(value: AwsSsoManagerError) -> Self {
+        Self::AwsSso(Box::new(value))
+    }
+}
+
+pub async fn exec_logout(config_dir: Option<&Path>, cache_dir: Option<&Path>) -> Result<(), Error> {
     let config_dir = resolve_config_dir(config_dir);
-    let sso_mgr = build_sso_mgr_cached(&config_dir, cache_dir);
+    let sso_mgr = build_sso_mgr_cached(&config_dir, cache_dir)?;
     sso_mgr.logout().await?;
     println!("INFO: Successfully logged out of all SSO sessions.");
     Ok(())
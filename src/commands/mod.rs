@@ -1,5 +1,6 @@
 pub mod alias;
 pub mod batch;
+pub mod config;
 pub mod core;
 pub mod init;
 pub mod logout;
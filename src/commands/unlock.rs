@@ -10,8 +10,8 @@ const LOCK_NAMES: [&str; 1] = ["aws-sso-create-token-lock"];
 pub fn exec_unlock(config_dir: Option<&Path>) -> Result<(), LockProviderError> {
     let config_dir = resolve_config_dir(config_dir);
 
-    let config =
-        AwsSsoConfig::load_config(&config_dir.join("config.json")).expect("Config should be valid");
+    let config = AwsSsoConfig::load_config(&config_dir)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
 
     for lock_name in LOCK_NAMES {
         let mut lock_provider = LockProvider::new(
@@ -1,4 +1,7 @@
+#[derive(Debug, Clone, Copy)]
 pub struct AssumeIdentifier<'a> {
     pub account: &'a str,
     pub role: &'a str,
+    /// Region pinned by an alias, None when the account and role were given directly
+    pub region: Option<&'a str>,
 }
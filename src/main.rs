@@ -12,6 +12,7 @@ use cmd::{Cli, Commands};
 use commands::{
     alias::exec_alias,
     batch::exec_batch,
+    config::exec_config,
     core::exec_core_commands,
     init::{self, ExecInitInputs},
     logout::exec_logout,
@@ -20,14 +21,25 @@ use commands::{
 };
 
 use std::error::Error;
+use std::process::ExitCode;
 
 fn error_to_string(error: impl Error) -> String {
     error.to_string()
 }
 
 #[tokio::main]
-async fn main() -> Result<(), String> {
-    let cli = Cli::parse();
+async fn main() -> ExitCode {
+    let cli = Cli::parse_from(cmd::credential_helper_args(std::env::args_os().collect()));
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
     match cli.command {
         Commands::Init {
             sso_start_url,
@@ -40,6 +52,9 @@ async fn main() -> Result<(), String> {
             create_token_retry_threshold,
             create_token_lock_decay_seconds,
             update,
+            non_interactive,
+            login,
+            import_aliases,
         } => {
             init::exec_init(ExecInitInputs {
                 config_dir,
@@ -50,16 +65,21 @@ async fn main() -> Result<(), String> {
                 initial_delay: initial_delay_seconds.map(std::time::Duration::from_secs),
                 retry_interval: retry_interval_seconds.map(std::time::Duration::from_secs),
                 create_token_lock_decay: create_token_lock_decay_seconds
-                    .map(|s| chrono::Duration::seconds(s as i64)),
+                    .map(std::time::Duration::from_secs),
                 create_token_retry_threshold,
                 update,
+                non_interactive,
+                login,
+                import_aliases,
             })
+            .await
             .map_err(error_to_string)?;
         }
         Commands::Core(command) => exec_core_commands(&command)
             .await
             .map_err(error_to_string)?,
-        Commands::Alias { subcommand } => exec_alias(subcommand).map_err(error_to_string)?,
+        Commands::Config { subcommand } => exec_config(subcommand).map_err(error_to_string)?,
+        Commands::Alias { subcommand } => exec_alias(subcommand).await.map_err(error_to_string)?,
         Commands::Sso { subcommand } => exec_sso(subcommand).await.map_err(error_to_string)?,
         Commands::Batch { subcommand } => exec_batch(subcommand).await.map_err(error_to_string)?,
         Commands::Unlock { config_dir } => {
@@ -0,0 +1,46 @@
+use super::atomic_write;
+use super::ini::IniDocument;
+use aws_sdk_sso::config::Credentials;
+use chrono::{DateTime, Utc};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Shared credentials file used by the AWS CLI and SDKs, ~/.aws/credentials
+pub fn default_credentials_file() -> PathBuf {
+    home::home_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(".aws")
+        .join("credentials")
+}
+
+/// Writes the credentials into the named profiles, keeping every other line of the file.
+pub fn write_credentials_profiles(
+    path: &Path,
+    profiles: &[(&str, &Credentials)],
+) -> io::Result<()> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let mut document = IniDocument::parse(&content);
+    for (profile, credentials) in profiles {
+        let expiration = credentials.expiry().map(|expiry| {
+            let dt: DateTime<Utc> = expiry.into();
+            dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        });
+        document.set_section(
+            profile,
+            &[
+                ("aws_access_key_id", Some(credentials.access_key_id())),
+                (
+                    "aws_secret_access_key",
+                    Some(credentials.secret_access_key()),
+                ),
+                ("aws_session_token", credentials.session_token()),
+                ("aws_expiration", expiration.as_deref()),
+            ],
+        );
+    }
+    atomic_write(path, &document.to_string())
+}
@@ -0,0 +1,130 @@
+/// Line based INI document that edits sections in place, keeping comments,
+/// blank lines and unrelated sections exactly as they were.
+#[derive(Debug, Default)]
+pub struct IniDocument {
+    lines: Vec<String>,
+}
+
+fn section_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(str::trim)
+}
+
+fn entry_key(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    if line.starts_with('#') || line.starts_with(';') {
+        return None;
+    }
+    line.split_once('=').map(|(key, _)| key.trim())
+}
+
+impl IniDocument {
+    pub fn parse(content: &str) -> Self {
+        IniDocument {
+            lines: content.lines().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Returns the start (header line) and end (exclusive) of the section.
+    fn section_range(&self, section: &str) -> Option<(usize, usize)> {
+        let start = self
+            .lines
+            .iter()
+            .position(|line| section_name(line) == Some(section))?;
+        let end = self.lines[start + 1..]
+            .iter()
+            .position(|line| section_name(line).is_some())
+            .map_or(self.lines.len(), |offset| start + 1 + offset);
+        Some((start, end))
+    }
+
+    /// Returns the value of the key in the section, the first one when repeated.
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        let (start, end) = self.section_range(section)?;
+        self.lines[start + 1..end].iter().find_map(|line| {
+            (entry_key(line) == Some(key))
+                .then(|| line.split_once('=').map(|(_, value)| value.trim()))
+                .flatten()
+        })
+    }
+
+    /// Sets or removes (None) the keys of the section, creating the section at the end when missing.
+    /// Existing keys keep their position, new keys are added after the last entry of the section.
+    pub fn set_section(&mut self, section: &str, values: &[(&str, Option<&str>)]) {
+        let Some((start, mut end)) = self.section_range(section) else {
+            if self
+                .lines
+                .last()
+                .is_some_and(|line| !line.trim().is_empty())
+            {
+                self.lines.push(String::new());
+            }
+            self.lines.push(format!("[{section}]"));
+            for (key, value) in values {
+                if let Some(value) = value {
+                    self.lines.push(format!("{key} = {value}"));
+                }
+            }
+            return;
+        };
+
+        for (key, value) in values {
+            let existing =
+                (start + 1..end).find(|&index| entry_key(&self.lines[index]) == Some(*key));
+            match (existing, value) {
+                (Some(index), Some(value)) => self.lines[index] = format!("{key} = {value}"),
+                (Some(index), None) => {
+                    self.lines.remove(index);
+                    end -= 1;
+                }
+                (None, Some(value)) => {
+                    let insert_at = (start + 1..end)
+                        .rev()
+                        .find(|&index| entry_key(&self.lines[index]).is_some())
+                        .map_or(start + 1, |index| index + 1);
+                    self.lines.insert(insert_at, format!("{key} = {value}"));
+                    end += 1;
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for IniDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_section_preserves_unrelated_content() {
+        let content = "# managed by hand\n[default]\naws_access_key_id = A\n\n[dev]\n; old token\naws_access_key_id = OLD\naws_session_token = T\n\n[prod]\nregion = eu-west-1\n";
+        let mut document = IniDocument::parse(content);
+        document.set_section(
+            "dev",
+            &[
+                ("aws_access_key_id", Some("NEW")),
+                ("aws_session_token", None),
+                ("aws_expiration", Some("2030-01-01T00:00:00Z")),
+            ],
+        );
+        document.set_section("ci", &[("aws_access_key_id", Some("CI"))]);
+        assert_eq!(document.get("prod", "region"), Some("eu-west-1"));
+        assert_eq!(document.get("dev", "aws_session_token"), None);
+
+        assert_eq!(
+            document.to_string(),
+            "# managed by hand\n[default]\naws_access_key_id = A\n\n[dev]\n; old token\naws_access_key_id = NEW\naws_expiration = 2030-01-01T00:00:00Z\n\n[prod]\nregion = eu-west-1\n\n[ci]\naws_access_key_id = CI\n"
+        );
+    }
+}
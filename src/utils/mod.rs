@@ -1,12 +1,22 @@
+pub mod credentials_file;
 pub mod elog;
 pub mod formatters;
+pub mod ini;
 pub mod lock;
+pub mod prompt;
+pub mod rate_limit;
+pub mod region;
+pub mod signed_request;
 pub mod worker;
 
 use crate::alias_providers::ProvideAliases;
 use crate::cmd::{AssumeInput, CommonArgs};
 use crate::common::AssumeIdentifier;
+use std::collections::hash_map::RandomState;
 use std::env;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 pub fn resolve_config_dir(config_dir: Option<&Path>) -> PathBuf {
@@ -19,10 +29,89 @@ pub fn resolve_config_dir(config_dir: Option<&Path>) -> PathBuf {
     )
 }
 
+/// Replaces the {name} placeholders of the template with the given values in a single pass,
+/// placeholders within substituted values are not expanded. Unknown placeholders are kept.
+pub fn render_placeholders(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = &rest[start + 1..];
+        let value = values.iter().find(|(key, _)| {
+            placeholder
+                .strip_prefix(key)
+                .is_some_and(|after| after.starts_with('}'))
+        });
+        match value {
+            Some((key, value)) => {
+                rendered.push_str(value);
+                rest = &placeholder[key.len() + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = placeholder;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Makes the value safe as a single path component, replacing path separators and characters
+/// invalid in file names with `_`. Values made only of dots, such as `..`, are replaced too.
+pub fn sanitize_path_component(value: &str) -> String {
+    if value.chars().all(|c| c == '.') {
+        return "_".repeat(value.len().max(1));
+    }
+    value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Writes the file through a temporary file in the same directory and renames it into place,
+/// so readers never observe a partially written file. The file is only readable by the owner.
+/// The temporary file is unique to the writer, so concurrent writers do not clobber each other.
+pub fn atomic_write(path: &Path, content: &str) -> io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let suffix = RandomState::new().build_hasher().finish();
+    let tmp_path = dir.join(format!(
+        ".{file_name}.{}.{suffix:016x}.aws-auth.tmp",
+        std::process::id()
+    ));
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp_path)?;
+    let written = file
+        .write_all(content.as_bytes())
+        .and_then(|_| file.sync_all())
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    written
+}
+
 #[derive(Debug)]
 pub enum AssumeIdResolverError<'a, PE: std::error::Error> {
     ProviderError(PE),
     AliasNotFoundError(&'a str),
+    MissingInput,
 }
 
 impl<PE: std::error::Error> std::fmt::Display for AssumeIdResolverError<'_, PE> {
@@ -34,15 +123,21 @@ impl<PE: std::error::Error> std::fmt::Display for AssumeIdResolverError<'_, PE>
             AssumeIdResolverError::AliasNotFoundError(alias) => {
                 write!(f, "Alias {alias} not found")
             }
+            AssumeIdResolverError::MissingInput => write!(
+                f,
+                "Provide --account and --role or --alias, or configure default_alias"
+            ),
         }
     }
 }
 
 impl<PE: std::error::Error> std::error::Error for AssumeIdResolverError<'_, PE> {}
 
+/// Resolves the account and role from the command line, falling back to the default alias.
 pub fn resolve_assume_identifier<'c, 'p: 'c, A: ProvideAliases>(
     provider: &'p mut A,
     common: &'c CommonArgs,
+    default_alias: Option<&'c str>,
 ) -> Result<AssumeIdentifier<'c>, AssumeIdResolverError<'c, A::Error>> {
     match &common.assume_input {
         AssumeInput {
@@ -52,20 +147,58 @@ pub fn resolve_assume_identifier<'c, 'p: 'c, A: ProvideAliases>(
         } => Ok(AssumeIdentifier {
             account: a,
             role: r,
+            region: None,
         }),
         AssumeInput {
             account: None,
             role: None,
-            alias: Some(l),
+            alias,
         } => {
+            let alias = alias
+                .as_deref()
+                .or(default_alias)
+                .ok_or(AssumeIdResolverError::MissingInput)?;
             provider
                 .load_aliases()
                 .map_err(AssumeIdResolverError::ProviderError)?;
             provider
-                .get_alias(l)
+                .get_alias(alias)
                 .map_err(AssumeIdResolverError::ProviderError)?
-                .ok_or(AssumeIdResolverError::AliasNotFoundError(l))
+                .ok_or(AssumeIdResolverError::AliasNotFoundError(alias))
         }
         _ => unreachable!("Clap should prevent code from reaching this branch"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_placeholders_in_a_single_pass() {
+        let values = [("account_name", "{role}"), ("role", "Admin")];
+        assert_eq!(
+            render_placeholders("{account_name}/{role}/{unknown}{", &values),
+            "{role}/Admin/{unknown}{"
+        );
+        assert_eq!(sanitize_path_component("../team/app"), ".._team_app");
+        assert_eq!(sanitize_path_component(".."), "__");
+    }
+
+    #[test]
+    fn test_atomic_write_replaces_file_without_leftovers() {
+        let dir = env::temp_dir().join(format!("aws-auth-atomic-{}", std::process::id()));
+        let path = dir.join("credentials");
+        atomic_write(&path, "first").unwrap();
+        atomic_write(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
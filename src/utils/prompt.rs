@@ -0,0 +1,45 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Returns true when stdin and stderr are attached to a terminal so prompts can be answered.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+fn read_answer(question: &str) -> io::Result<String> {
+    eprint!("{question} ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "No answer provided",
+        ));
+    }
+    Ok(answer.trim().to_string())
+}
+
+/// Asks a yes/no question on stderr, an empty answer selects the default.
+pub fn confirm(question: &str, default: bool) -> io::Result<bool> {
+    let hint = if default { "[Y/n]" } else { "[y/N]" };
+    let answer = read_answer(&format!("{question} {hint}"))?.to_lowercase();
+    Ok(match answer.as_str() {
+        "" => default,
+        "y" | "yes" => true,
+        _ => false,
+    })
+}
+
+/// Asks for a value on stderr until a non-empty answer is given, an empty answer selects the default.
+pub fn prompt(question: &str, default: Option<&str>) -> io::Result<String> {
+    loop {
+        let answer = match default {
+            Some(default) => read_answer(&format!("{question} [{default}]:"))?,
+            None => read_answer(&format!("{question}:"))?,
+        };
+        match (answer.is_empty(), default) {
+            (false, _) => return Ok(answer),
+            (true, Some(default)) => return Ok(default.to_string()),
+            (true, None) => continue,
+        }
+    }
+}
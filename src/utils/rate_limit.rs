@@ -0,0 +1,113 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Token bucket holding up to `capacity` tokens, refilled continuously at `rate` tokens per second
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        // Bursts are limited to one second worth of requests
+        let capacity = rate.max(1.0);
+        Self {
+            capacity,
+            rate,
+            tokens: capacity,
+            refilled_at: now,
+        }
+    }
+
+    /// Takes a token, returns how long to wait for the next one when the bucket is empty.
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.refilled_at)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+}
+
+/// Rate limiter shared by concurrent tasks, each request takes a token of the bucket.
+/// A rate of zero or less disables the limit.
+#[derive(Debug)]
+pub struct RateLimiter {
+    bucket: Option<Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_second: f64) -> Self {
+        Self {
+            bucket: (requests_per_second > 0.0)
+                .then(|| Mutex::new(TokenBucket::new(requests_per_second, Instant::now()))),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let Some(bucket) = &self.bucket else {
+            return;
+        };
+        loop {
+            let wait = bucket
+                .lock()
+                .expect("Token bucket updates cannot panic")
+                .take(Instant::now());
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+/// Full jitter exponential backoff, a random delay up to `base * 2^attempt` capped at `cap`.
+pub fn jittered_backoff(attempt: u32, base: Duration, cap: Duration) -> Duration {
+    let ceiling = base.saturating_mul(2u32.saturating_pow(attempt)).min(cap);
+    // RandomState is seeded randomly per instance, good enough for jitter without a rand dependency
+    let random = RandomState::new().build_hasher().finish();
+    ceiling.mul_f64(random as f64 / u64::MAX as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_refills_at_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, start);
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), None);
+        assert_eq!(bucket.take(start), Some(Duration::from_millis(500)));
+        assert_eq!(bucket.take(start + Duration::from_millis(500)), None);
+        // The bucket never holds more than its capacity
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.take(later), None);
+        assert_eq!(bucket.take(later), None);
+        assert!(bucket.take(later).is_some());
+    }
+
+    #[test]
+    fn test_jittered_backoff_is_capped() {
+        let base = Duration::from_millis(200);
+        let cap = Duration::from_secs(5);
+        for attempt in 0..40 {
+            let delay = jittered_backoff(attempt, base, cap);
+            assert!(delay <= cap);
+            assert!(delay <= base.saturating_mul(2u32.saturating_pow(attempt)));
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use super::ini::IniDocument;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// AWS partition with the regions known at build time
+#[derive(Debug)]
+pub struct Partition {
+    pub id: &'static str,
+    /// Domain of the regional service endpoints, e.g. sts.<region>.<dns_suffix>
+    pub dns_suffix: &'static str,
+    pub regions: &'static [&'static str],
+    /// Regions disabled in accounts until opted in
+    pub opt_in_regions: &'static [&'static str],
+}
+
+impl Partition {
+    /// Regions enabled in every account of the partition, excluding opt-in regions.
+    pub fn default_regions(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.regions
+            .iter()
+            .copied()
+            .filter(|region| !self.opt_in_regions.contains(region))
+    }
+}
+
+pub const PARTITIONS: [Partition; 8] = [
+    Partition {
+        id: "aws",
+        dns_suffix: "amazonaws.com",
+        regions: &[
+            "af-south-1",
+            "ap-east-1",
+            "ap-east-2",
+            "ap-northeast-1",
+            "ap-northeast-2",
+            "ap-northeast-3",
+            "ap-south-1",
+            "ap-south-2",
+            "ap-southeast-1",
+            "ap-southeast-2",
+            "ap-southeast-3",
+            "ap-southeast-4",
+            "ap-southeast-5",
+            "ap-southeast-6",
+            "ap-southeast-7",
+            "ca-central-1",
+            "ca-west-1",
+            "eu-central-1",
+            "eu-central-2",
+            "eu-north-1",
+            "eu-south-1",
+            "eu-south-2",
+            "eu-west-1",
+            "eu-west-2",
+            "eu-west-3",
+            "il-central-1",
+            "me-central-1",
+            "me-south-1",
+            "mx-central-1",
+            "sa-east-1",
+            "us-east-1",
+            "us-east-2",
+            "us-west-1",
+            "us-west-2",
+        ],
+        opt_in_regions: &[
+            "af-south-1",
+            "ap-east-1",
+            "ap-east-2",
+            "ap-south-2",
+            "ap-southeast-3",
+            "ap-southeast-4",
+            "ap-southeast-5",
+            "ap-southeast-6",
+            "ap-southeast-7",
+            "ca-west-1",
+            "eu-central-2",
+            "eu-south-1",
+            "eu-south-2",
+            "il-central-1",
+            "me-central-1",
+            "me-south-1",
+            "mx-central-1",
+        ],
+    },
+    Partition {
+        id: "aws-cn",
+        dns_suffix: "amazonaws.com.cn",
+        regions: &["cn-north-1", "cn-northwest-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-us-gov",
+        dns_suffix: "amazonaws.com",
+        regions: &["us-gov-east-1", "us-gov-west-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-iso",
+        dns_suffix: "c2s.ic.gov",
+        regions: &["us-iso-east-1", "us-iso-west-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-iso-b",
+        dns_suffix: "sc2s.sgov.gov",
+        regions: &["us-isob-east-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-iso-e",
+        dns_suffix: "cloud.adc-e.uk",
+        regions: &["eu-isoe-west-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-iso-f",
+        dns_suffix: "csp.hci.ic.gov",
+        regions: &["us-isof-east-1", "us-isof-south-1"],
+        opt_in_regions: &[],
+    },
+    Partition {
+        id: "aws-eusc",
+        dns_suffix: "amazonaws.eu",
+        regions: &["eusc-de-east-1"],
+        opt_in_regions: &[],
+    },
+];
+
+pub fn find_partition(region: &str) -> Option<&'static Partition> {
+    PARTITIONS.iter().find(|p| p.regions.contains(&region))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.bytes().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+/// Checks the region against the known partitions, suggesting the closest known region on failure.
+pub fn validate_region(region: &str) -> Result<&'static Partition, String> {
+    if let Some(partition) = find_partition(region) {
+        return Ok(partition);
+    }
+    let closest = PARTITIONS
+        .iter()
+        .flat_map(|p| p.regions.iter())
+        .map(|known| (edit_distance(region, known), known))
+        .min_by_key(|(distance, _)| *distance)
+        .filter(|(distance, _)| *distance <= 3);
+    match closest {
+        Some((_, suggestion)) => Err(format!(
+            "Unknown AWS region {region:?}, did you mean {suggestion:?}?"
+        )),
+        None => Err(format!(
+            "Unknown AWS region {region:?}, expected a region such as \"us-east-1\" in one of the partitions: {}",
+            PARTITIONS.iter().map(|p| p.id).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+/// Region of the active profile, AWS_PROFILE or default, in the AWS config file,
+/// AWS_CONFIG_FILE or ~/.aws/config.
+fn aws_config_region() -> Option<String> {
+    let path = env::var_os("AWS_CONFIG_FILE")
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".aws").join("config")))?;
+    let content = fs::read_to_string(path).ok()?;
+    let profile = env::var("AWS_PROFILE")
+        .ok()
+        .filter(|profile| !profile.is_empty())
+        .unwrap_or_else(|| "default".to_string());
+    let section = if profile == "default" {
+        profile
+    } else {
+        format!("profile {profile}")
+    };
+    IniDocument::parse(&content)
+        .get(&section, "region")
+        .filter(|region| !region.is_empty())
+        .map(ToString::to_string)
+}
+
+/// Resolves the region for a command, first match wins:
+/// command line, alias, region pinned by .aws-auth.toml or AWS_AUTH_REGION, AWS_REGION,
+/// AWS_DEFAULT_REGION, region of the profile in ~/.aws/config, config.toml region, SSO region.
+/// The resolved region is validated and errors name the source it came from.
+pub fn resolve_region(
+    cli_region: Option<&str>,
+    alias_region: Option<&str>,
+    pinned_region: Option<&str>,
+    default_region: Option<&str>,
+    sso_region: &str,
+) -> Result<String, String> {
+    resolve_region_from(
+        cli_region,
+        alias_region,
+        pinned_region,
+        default_region,
+        sso_region,
+        |var| env::var(var).ok(),
+        aws_config_region,
+    )
+}
+
+fn resolve_region_from(
+    cli_region: Option<&str>,
+    alias_region: Option<&str>,
+    pinned_region: Option<&str>,
+    default_region: Option<&str>,
+    sso_region: &str,
+    env_var: impl Fn(&str) -> Option<String>,
+    aws_config_region: impl FnOnce() -> Option<String>,
+) -> Result<String, String> {
+    let env_region = |var: &'static str| {
+        env_var(var)
+            .filter(|region| !region.is_empty())
+            .map(|region| (region, var))
+    };
+    let (region, source) = cli_region
+        .map(|region| (region.to_string(), "--region"))
+        .or_else(|| alias_region.map(|region| (region.to_string(), "alias")))
+        .or_else(|| pinned_region.map(|region| (region.to_string(), "config region")))
+        .or_else(|| env_region("AWS_REGION"))
+        .or_else(|| env_region("AWS_DEFAULT_REGION"))
+        .or_else(|| aws_config_region().map(|region| (region, "~/.aws/config")))
+        .or_else(|| default_region.map(|region| (region.to_string(), "config region")))
+        .unwrap_or_else(|| (sso_region.to_string(), "config sso_region"));
+    validate_region(&region).map_err(|err| format!("{err} (from {source})"))?;
+    Ok(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_region_precedence_and_validation() {
+        assert_eq!(
+            resolve_region(
+                Some("us-east-1"),
+                Some("eu-west-1"),
+                None,
+                None,
+                "eu-west-2"
+            )
+            .unwrap(),
+            "us-east-1"
+        );
+        assert_eq!(
+            resolve_region(
+                None,
+                Some("eu-west-1"),
+                Some("us-west-2"),
+                None,
+                "eu-west-2"
+            )
+            .unwrap(),
+            "eu-west-1"
+        );
+        let err = resolve_region(None, Some("eu-wset-1"), None, None, "eu-west-2").unwrap_err();
+        assert!(err.contains("did you mean \"eu-west-1\""));
+        assert!(err.contains("(from alias)"));
+    }
+
+    #[test]
+    fn test_default_regions_exclude_opt_in_regions() {
+        let partition = find_partition("eu-west-1").unwrap();
+        let defaults = partition.default_regions().collect::<Vec<_>>();
+        assert!(defaults.contains(&"us-east-1"));
+        assert!(!defaults.contains(&"af-south-1"));
+        assert!(partition
+            .opt_in_regions
+            .iter()
+            .all(|region| partition.regions.contains(region)));
+    }
+
+    #[test]
+    fn test_aws_variables_precede_default_region() {
+        let env = |var: &str| match var {
+            "AWS_REGION" => Some("us-east-2".to_string()),
+            "AWS_DEFAULT_REGION" => Some("us-west-1".to_string()),
+            _ => None,
+        };
+        let default_region_env = |var: &str| match var {
+            "AWS_DEFAULT_REGION" => Some("us-west-1".to_string()),
+            _ => None,
+        };
+        let no_env = |_: &str| None;
+        let aws_config = || Some("ap-southeast-2".to_string());
+        let resolve = |pinned,
+                       env: &dyn Fn(&str) -> Option<String>,
+                       aws_config: &dyn Fn() -> Option<String>| {
+            resolve_region_from(
+                None,
+                None,
+                pinned,
+                Some("eu-west-1"),
+                "eu-west-2",
+                env,
+                aws_config,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            resolve(Some("ca-central-1"), &env, &aws_config),
+            "ca-central-1"
+        );
+        assert_eq!(resolve(None, &env, &aws_config), "us-east-2");
+        assert_eq!(resolve(None, &default_region_env, &aws_config), "us-west-1");
+        assert_eq!(resolve(None, &no_env, &aws_config), "ap-southeast-2");
+        assert_eq!(resolve(None, &no_env, &|| None), "eu-west-1");
+        assert_eq!(
+            resolve_region_from(None, None, None, None, "eu-west-2", no_env, || None).unwrap(),
+            "eu-west-2"
+        );
+    }
+}
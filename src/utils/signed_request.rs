@@ -0,0 +1,181 @@
+use aws_sdk_sso::config::Credentials;
+use aws_sigv4::http_request::{self, SignableBody, SignableRequest, SigningError, SigningSettings};
+use aws_sigv4::sign;
+use aws_smithy_runtime_api::client::identity::Identity;
+use std::time::SystemTime;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Error signing request: {0}")]
+    Signing(#[from] SigningError),
+    #[error("Error sending request: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Request to {0} failed with status {1}: {2}")]
+    Status(String, u16, String),
+}
+
+pub struct SignedRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+    pub headers: &'a [(&'a str, &'a str)],
+    pub body: &'a [u8],
+}
+
+/// Signs the request with SigV4 header signing, sends it and returns the response body.
+/// Non success statuses are returned as errors including the response body.
+pub async fn send_signed_request(
+    credentials: &Credentials,
+    request: SignedRequest<'_>,
+) -> Result<String, Error> {
+    let identity = Identity::from(credentials.to_owned());
+    let params = sign::v4::SigningParams::builder()
+        .identity(&identity)
+        .region(request.region)
+        .name(request.service)
+        .time(SystemTime::now())
+        .settings(SigningSettings::default())
+        .build()
+        .expect("there should not be any build errors");
+
+    let signable = SignableRequest::new(
+        request.method,
+        request.url,
+        request.headers.iter().copied(),
+        SignableBody::Bytes(request.body),
+    )?;
+    let (signing_instructions, _) =
+        http_request::sign(signable, &http_request::SigningParams::V4(params))?.into_parts();
+
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())
+        .expect("Request methods are static and valid");
+    let mut builder = reqwest::Client::new()
+        .request(method, request.url)
+        .body(request.body.to_vec());
+    for (name, value) in request.headers.iter().copied() {
+        builder = builder.header(name, value);
+    }
+    for (name, value) in signing_instructions.headers() {
+        builder = builder.header(name, value);
+    }
+
+    let response = builder.send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::Status(
+            request.url.to_string(),
+            status.as_u16(),
+            body,
+        ));
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Answers a single request with the status and body, returns the raw request received.
+    async fn stub_server(
+        status: &'static str,
+        body: &'static str,
+    ) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let read = stream.read(&mut buf).await.unwrap();
+                received.extend_from_slice(&buf[..read]);
+                let request = String::from_utf8_lossy(&received).to_string();
+                if let Some((head, content)) = request.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())
+                                .flatten()
+                        })
+                        .unwrap_or(0);
+                    if content.len() >= length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(received).unwrap()
+        });
+        (url, handle)
+    }
+
+    fn credentials() -> Credentials {
+        Credentials::new(
+            "AKIDEXAMPLE",
+            "SECRET",
+            Some("TOKEN".to_string()),
+            None,
+            "test",
+        )
+    }
+
+    #[tokio::test]
+    async fn test_signed_request_carries_sigv4_headers() {
+        let (url, server) = stub_server("200 OK", r#"{"cluster":{}}"#).await;
+        let body = send_signed_request(
+            &credentials(),
+            SignedRequest {
+                method: "POST",
+                url: &url,
+                region: "eu-west-1",
+                service: "ecr",
+                headers: &[("x-amz-target", "Test.Operation")],
+                body: b"{}",
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(body, r#"{"cluster":{}}"#);
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.starts_with("post / http/1.1"));
+        assert!(request.contains("authorization: aws4-hmac-sha256 credential=akidexample/"));
+        assert!(request.contains("/eu-west-1/ecr/aws4_request"));
+        assert!(request.contains("x-amz-security-token: token"));
+        assert!(request.contains("x-amz-target: test.operation"));
+        assert!(request.ends_with("\r\n\r\n{}"));
+    }
+
+    #[tokio::test]
+    async fn test_signed_request_maps_error_status() {
+        let (url, server) = stub_server("403 Forbidden", "AccessDenied").await;
+        let err = send_signed_request(
+            &credentials(),
+            SignedRequest {
+                method: "GET",
+                url: &url,
+                region: "eu-west-1",
+                service: "eks",
+                headers: &[],
+                body: b"",
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, Error::Status(_, 403, body) if body == "AccessDenied"));
+        server.await.unwrap();
+    }
+}
@@ -5,6 +5,7 @@ use std::panic::UnwindSafe;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 pub trait Job: Send + UnwindSafe + 'static {
@@ -28,10 +29,10 @@ pub enum JobError<J: Job> {
     Panicked(String),
 }
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub struct JobResult<J: Job> {
     pub job_id: String,
+    pub duration: Duration,
     pub result: Result<J::Output, JobError<J>>,
 }
 
@@ -40,10 +41,12 @@ type SharedJMReceiver<J> = Arc<Mutex<Receiver<JobMessage<J>>>>;
 enum JobResultMessage<J: Job> {
     Result {
         job_id: String,
+        duration: Duration,
         job_result: Result<J::Output, J::Error>,
     },
     Panicked {
         job_id: String,
+        duration: Duration,
         panic_error: String,
     },
     Terminated(usize),
@@ -88,11 +91,15 @@ where
                                 elog!(debug, "[{id}] Received Job with Id: {jid}",);
                                 // Job.execute consumes the Job object by taking ownership of it to be UnwindSafe.
                                 // Execute the job, if it panics, catch the panic and continue
-                                match catch_unwind(|| job.execute()) {
+                                let started = Instant::now();
+                                let outcome = catch_unwind(|| job.execute());
+                                let duration = started.elapsed();
+                                match outcome {
                                     Ok(job_result) => {
                                         if sender
                                             .send(JobResultMessage::Result {
                                                 job_id: jid,
+                                                duration,
                                                 job_result,
                                             })
                                             .is_err()
@@ -117,6 +124,7 @@ where
                                         if sender
                                             .send(JobResultMessage::Panicked {
                                                 job_id: jid,
+                                                duration,
                                                 panic_error: panic_message,
                                             })
                                             .is_err()
@@ -206,9 +214,14 @@ where
         let mut results = Vec::new();
         while terminated < self.num_workers {
             match self.result_receiver.recv().unwrap() {
-                JobResultMessage::Result { job_id, job_result } => {
+                JobResultMessage::Result {
+                    job_id,
+                    duration,
+                    job_result,
+                } => {
                     results.push(JobResult::<J> {
                         job_id,
+                        duration,
                         result: job_result.map_err(JobError::Error),
                     });
                 }
@@ -221,10 +234,12 @@ where
                 }
                 JobResultMessage::Panicked {
                     job_id,
+                    duration,
                     panic_error,
                 } => {
                     results.push(JobResult {
                         job_id,
+                        duration,
                         result: Err(JobError::Panicked(panic_error)),
                     });
                 }